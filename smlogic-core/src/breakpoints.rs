@@ -0,0 +1,142 @@
+use crate::simulator::SimState;
+
+/// something that pauses the simulation when it happens
+#[derive(Clone, PartialEq)]
+pub enum Breakpoint {
+    TurnsOn {
+        name: String,
+        index: usize,
+    },
+    BusEquals {
+        name: String,
+        bits: Vec<usize>, // simulation indexes, most significant bit first
+        value: u64,
+    },
+    Tick(u64),
+}
+
+impl Breakpoint {
+    pub fn to_label(&self) -> String {
+        match self {
+            Breakpoint::TurnsOn { name, .. } => format!("{} turns on", name),
+            Breakpoint::BusEquals { name, value, .. } => format!("{} = {}", name, value),
+            Breakpoint::Tick(tick) => format!("tick {}", tick),
+        }
+    }
+
+    /// simulation indexes of the parts it watches
+    pub fn indexes(&self) -> Vec<usize> {
+        match self {
+            Breakpoint::TurnsOn { index, .. } => vec![*index],
+            Breakpoint::BusEquals { bits, .. } => bits.clone(),
+            Breakpoint::Tick(_) => Vec::new(),
+        }
+    }
+
+    /// checked right after a tick. only fires when the condition becomes true since `before`
+    /// so resuming doesnt instantly pause again
+    pub fn hit(&self, state: &SimState, before: &[bool]) -> bool {
+        match self {
+            Breakpoint::TurnsOn { index, .. } => state.part_outputs[*index] && !before[*index],
+            Breakpoint::BusEquals { bits, value, .. } => {
+                bus_value(bits, &state.part_outputs) == *value && bus_value(bits, before) != *value
+            }
+            Breakpoint::Tick(tick) => state.tick == *tick,
+        }
+    }
+}
+
+/// reads the bits as a number, first bit is the most significant
+pub fn bus_value(bits: &[usize], outputs: &[bool]) -> u64 {
+    bits.iter()
+        .fold(0, |value, &i| (value << 1) | outputs[i] as u64)
+}
+
+impl SimState {
+    /// stops the simulation if any breakpoint was hit by the last tick, returns if it did.
+    /// compares against the outputs from the last check, not `prev_outputs`, since inputs
+    /// that get clicked or driven by a stimulus set both
+    pub fn check_breakpoints(&mut self) -> bool {
+        if self.breakpoints.is_empty() {
+            self.breakpoint_outputs.clear();
+            return false;
+        }
+        let before = if self.breakpoint_outputs.len() == self.part_outputs.len() {
+            &self.breakpoint_outputs
+        } else {
+            &self.prev_outputs
+        };
+        let hit = self.breakpoints.iter().position(|b| b.hit(self, before));
+        self.breakpoint_outputs.clone_from(&self.part_outputs);
+        if let Some(i) = hit {
+            self.breakpoint_hit = Some(i);
+            self.running = false;
+            return true;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::CanvasSnapshot;
+    use crate::parts::{Part, PartType};
+    use crate::stimulus::Stimulus;
+    use emath::Pos2;
+
+    fn input_sim() -> (SimState, usize) {
+        let mut canvas = CanvasSnapshot::new();
+        let input = Part::add(
+            PartType::Input,
+            &mut canvas,
+            Pos2::ZERO,
+            None,
+            &mut Vec::new(),
+        );
+        let state = SimState::from_canvas_snapshot(&mut canvas, &mut Vec::new());
+        let index = canvas.parts[&input].simulation_index.unwrap();
+        (state, index)
+    }
+
+    /// ticks like the simulation loop does, returns the ticks a breakpoint was hit on
+    fn run(state: &mut SimState, ticks: u64) -> Vec<u64> {
+        let mut hits = Vec::new();
+        for _ in 0..ticks {
+            state.tick();
+            state.apply_stimuli();
+            if state.check_breakpoints() {
+                hits.push(state.tick);
+            }
+        }
+        hits
+    }
+
+    #[test]
+    fn clocked_inputs_hit_turns_on() {
+        let (mut state, index) = input_sim();
+        state.breakpoints.push(Breakpoint::TurnsOn {
+            name: "clock".to_string(),
+            index,
+        });
+        state.set_stimulus(index, Some(Stimulus::Clock { period: 4, duty: 2 }));
+        // on at tick 0 already, so only the next rising edges count
+        assert_eq!(run(&mut state, 12), vec![4, 8, 12]);
+    }
+
+    #[test]
+    fn clicked_inputs_hit_bus_equals() {
+        let (mut state, index) = input_sim();
+        state.breakpoints.push(Breakpoint::BusEquals {
+            name: "bus".to_string(),
+            bits: vec![index],
+            value: 1,
+        });
+        assert!(run(&mut state, 3).is_empty());
+        state.set_output(index, true);
+        assert_eq!(run(&mut state, 3), vec![4]);
+        state.set_output(index, false);
+        state.set_output(index, true);
+        assert!(run(&mut state, 1).is_empty()); // it was already on at the last check
+    }
+}
@@ -0,0 +1,178 @@
+use crate::breakpoints::bus_value;
+use crate::simulator::SimState;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub const MAX_BUS_WIDTH: usize = 64;
+
+/// how a buses value is shown and typed in
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum BusFormat {
+    #[default]
+    Hex,
+    Decimal,
+    Signed,
+    Ascii,
+}
+
+impl BusFormat {
+    pub const FORMATS: &[Self] = &[Self::Hex, Self::Decimal, Self::Signed, Self::Ascii];
+
+    pub fn to_label(&self) -> &'static str {
+        match self {
+            BusFormat::Hex => "Hex",
+            BusFormat::Decimal => "Decimal",
+            BusFormat::Signed => "Signed",
+            BusFormat::Ascii => "ASCII",
+        }
+    }
+
+    pub fn format(&self, value: u64, width: usize) -> String {
+        match self {
+            BusFormat::Hex => format!("0x{:0digits$X}", value, digits = width.div_ceil(4)),
+            BusFormat::Decimal => value.to_string(),
+            BusFormat::Signed => to_signed(value, width).to_string(),
+            BusFormat::Ascii => {
+                // one character per byte, highest byte first
+                (0..width.div_ceil(8))
+                    .rev()
+                    .map(|byte| match (value >> (byte * 8)) as u8 {
+                        c @ 0x20..=0x7e => c as char,
+                        _ => '.',
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// reads a typed in value, none if it isnt valid or doesnt fit in the bus
+    pub fn parse(&self, text: &str, width: usize) -> Option<u64> {
+        let text = text.trim();
+        let value = match self {
+            BusFormat::Hex => {
+                let digits = text.trim_start_matches("0x").trim_start_matches("0X");
+                u64::from_str_radix(digits, 16).ok()?
+            }
+            BusFormat::Decimal => text.parse().ok()?,
+            BusFormat::Signed => {
+                let value: i64 = text.parse().ok()?;
+                let max = (mask(width) >> 1) as i64;
+                if value < -max - 1 || value > max {
+                    return None;
+                }
+                value as u64 & mask(width)
+            }
+            BusFormat::Ascii => {
+                if text.len() > width.div_ceil(8) || !text.is_ascii() {
+                    return None;
+                }
+                text.bytes()
+                    .fold(0, |value, byte| (value << 8) | byte as u64)
+            }
+        };
+        (value & !mask(width) == 0).then_some(value)
+    }
+}
+
+fn mask(width: usize) -> u64 {
+    if width >= 64 {
+        u64::MAX
+    } else {
+        (1 << width) - 1
+    }
+}
+
+fn to_signed(value: u64, width: usize) -> i64 {
+    if width == 0 || width >= 64 {
+        return value as i64;
+    }
+    let shift = 64 - width;
+    ((value << shift) as i64) >> shift
+}
+
+/// a named group of parts read as one number, saved with the module. bits are part paths (see
+/// `RawCanvasData::part_paths`), most significant bit first
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bus {
+    pub name: String,
+    pub bits: Vec<Vec<u64>>,
+    #[serde(default)]
+    pub format: BusFormat,
+}
+
+impl Bus {
+    /// simulation indexes of every bit, none if any of them arent simulated
+    pub fn indexes(&self, path_indexes: &HashMap<Vec<u64>, usize>) -> Option<Vec<usize>> {
+        self.bits
+            .iter()
+            .map(|path| path_indexes.get(path).copied())
+            .collect()
+    }
+
+    pub fn value(&self, path_indexes: &HashMap<Vec<u64>, usize>, outputs: &[bool]) -> Option<u64> {
+        let indexes = self.indexes(path_indexes)?;
+        if indexes.iter().any(|&i| i >= outputs.len()) {
+            return None;
+        }
+        Some(bus_value(&indexes, outputs))
+    }
+}
+
+impl SimState {
+    /// sets every bit of a bus at once, like toggling each of them. first bit is the most
+    /// significant
+    pub fn set_bus(&mut self, bits: &[usize], value: u64) {
+        for (i, &bit) in bits.iter().rev().enumerate() {
+            self.set_output(bit, i < 64 && (value >> i) & 1 == 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_values() {
+        assert_eq!(BusFormat::Hex.format(0xa, 8), "0x0A");
+        assert_eq!(BusFormat::Hex.format(0x1ff, 9), "0x1FF");
+        assert_eq!(BusFormat::Decimal.format(200, 8), "200");
+        assert_eq!(BusFormat::Signed.format(200, 8), "-56");
+        assert_eq!(BusFormat::Signed.format(100, 8), "100");
+        assert_eq!(BusFormat::Signed.format(u64::MAX, 64), "-1");
+        assert_eq!(BusFormat::Ascii.format(0x4869, 16), "Hi");
+        assert_eq!(BusFormat::Ascii.format(0x0741, 16), ".A");
+    }
+
+    #[test]
+    fn parses_values() {
+        assert_eq!(BusFormat::Hex.parse("0xff", 8), Some(255));
+        assert_eq!(BusFormat::Hex.parse(" FF ", 8), Some(255));
+        assert_eq!(BusFormat::Hex.parse("1ff", 8), None);
+        assert_eq!(BusFormat::Decimal.parse("256", 8), None);
+        assert_eq!(BusFormat::Signed.parse("-128", 8), Some(0x80));
+        assert_eq!(BusFormat::Signed.parse("-129", 8), None);
+        assert_eq!(BusFormat::Signed.parse("128", 8), None);
+        assert_eq!(BusFormat::Ascii.parse("Hi", 16), Some(0x4869));
+        assert_eq!(BusFormat::Ascii.parse("Hi!", 16), None);
+        assert_eq!(BusFormat::Ascii.parse("é", 16), None);
+        assert_eq!(BusFormat::Decimal.parse("nope", 8), None);
+    }
+
+    #[test]
+    fn formatted_values_parse_back() {
+        for width in [1, 4, 7, 8, 16, 33, 63, 64] {
+            for value in [0, 1, 0x41, 0x7f, 0x80, u64::MAX] {
+                let value = value & mask(width);
+                for format in BusFormat::FORMATS {
+                    let text = format.format(value, width);
+                    // ascii can only show printable bytes
+                    if *format == BusFormat::Ascii && text.contains('.') {
+                        continue;
+                    }
+                    assert_eq!(format.parse(&text, width), Some(value), "{}", text);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use crate::bus::Bus;
+use crate::connections::Connection;
+use crate::error::ModuleError;
+use crate::memory::Memory;
+use crate::parts::{Part, PartData};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+const MAX_PATH_LEN: usize = 20;
+
+pub fn path_to_string(path: PathBuf, project_folder: Option<PathBuf>) -> String {
+    let out = if let Some(folder) = project_folder {
+        path.strip_prefix(folder)
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|_| path.to_string_lossy().to_string())
+    } else {
+        path.to_string_lossy().to_string()
+    };
+
+    let file_name_len = path
+        .file_name()
+        .map(|n| n.to_string_lossy().chars().count())
+        .unwrap_or(0);
+    let max_len = MAX_PATH_LEN.max(file_name_len);
+    if out.chars().count() <= max_len {
+        out
+    } else {
+        format!(
+            "...{}",
+            out.chars()
+                .rev()
+                .take(max_len)
+                .collect::<String>()
+                .chars()
+                .rev()
+                .collect::<String>()
+        )
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize, PartialEq)]
+pub struct CanvasSnapshot {
+    pub parts: HashMap<u64, Part>,
+    pub connections: Vec<Connection>,
+    pub next_id: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub buses: Vec<Bus>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub memories: Vec<Memory>,
+}
+
+// saving/loading modules
+impl CanvasSnapshot {
+    pub fn new() -> Self {
+        Self {
+            parts: HashMap::new(),
+            connections: Vec::new(),
+            next_id: 0,
+            buses: Vec::new(),
+            memories: Vec::new(),
+        }
+    }
+
+    pub fn save(&self, path: PathBuf) -> Result<(), ModuleError> {
+        let json = serde_json::to_value(self)?;
+        let pretty = serde_json::to_string_pretty(&json)?;
+        std::fs::write(path, pretty)?;
+        Ok(())
+    }
+
+    /// loads a module file, any sub modules that fail to load get pushed to `errors` and marked
+    /// problematic instead of failing the whole load
+    pub fn load(
+        path: PathBuf,
+        project_path: Option<PathBuf>,
+        errors: &mut Vec<ModuleError>,
+        ancestors: Vec<PathBuf>,
+    ) -> Result<Self, ModuleError> {
+        let contents = std::fs::read_to_string(path)?;
+        let json: serde_json::Value = serde_json::from_str(&contents)?;
+
+        let mut canvas_snapshot: Self = serde_json::from_value(json)?;
+        canvas_snapshot.reload_modules(project_path, errors, ancestors);
+        Ok(canvas_snapshot)
+    }
+
+    pub fn reload_modules(
+        &mut self,
+        project_path: Option<PathBuf>,
+        errors: &mut Vec<ModuleError>,
+        ancestors: Vec<PathBuf>,
+    ) {
+        for part in self.parts.values_mut() {
+            if let PartData::Module(data) = &mut part.part_data {
+                data.reload(project_path.clone(), errors, ancestors.clone());
+            }
+        }
+    }
+
+    /// paths of every module that loaded fine, nested ones before the module holding them. the
+    /// gui reports these after loading, failed ones end up in the errors instead
+    pub fn loaded_modules(&self) -> Vec<PathBuf> {
+        let mut loaded = Vec::new();
+        for part in self.parts.values() {
+            if let PartData::Module(module) = &part.part_data {
+                loaded.extend(module.loaded_modules());
+            }
+        }
+        loaded
+    }
+}
+
+impl CanvasSnapshot {
+    /// labels of every part along a part path (see `RawCanvasData::part_paths`), outermost
+    /// module first. stops early if a part isnt there anymore
+    pub fn path_labels(&self, path: &[u64]) -> Vec<String> {
+        let mut labels = Vec::new();
+        let mut canvas = self;
+        for id in path {
+            let Some(part) = canvas.parts.get(id) else {
+                break;
+            };
+            labels.push(part.label.clone());
+            if let PartData::Module(module) = &part.part_data {
+                canvas = &module.canvas_snapshot;
+            }
+        }
+        labels
+    }
+}
+
+impl Default for CanvasSnapshot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,314 @@
+use crate::simulator::{PartType, SimState};
+use std::collections::VecDeque;
+
+/// which code runs the simulation, they all give the exact same results
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Engine {
+    Simple,
+    EventDriven,
+}
+
+impl Engine {
+    pub const ENGINES: &[Self] = &[Self::Simple, Self::EventDriven];
+
+    pub fn to_label(&self) -> &'static str {
+        match self {
+            Engine::Simple => "Simple",
+            Engine::EventDriven => "Event driven",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    And,
+    Or,
+    Xor,
+    Nand,
+    Nor,
+    Xnor,
+    Timer,
+    Held, // no inputs, keeps whatever it was set to
+}
+
+fn get_bit(bits: &[u64], i: usize) -> bool {
+    bits[i >> 6] >> (i & 63) & 1 == 1
+}
+
+fn set_bit(bits: &mut [u64], i: usize, value: bool) {
+    let word = &mut bits[i >> 6];
+    *word = (*word & !(1 << (i & 63))) | ((value as u64) << (i & 63));
+}
+
+fn pack(values: &[bool]) -> Vec<u64> {
+    let mut bits = vec![0; values.len().div_ceil(64)];
+    for (i, value) in values.iter().enumerate() {
+        set_bit(&mut bits, i, *value);
+    }
+    bits
+}
+
+/// the simulation flattened into arrays for the event driven engine. inputs and fanout are
+/// stored as one big list with start offsets per part and outputs are packed 64 to a u64. every
+/// gate has a one tick delay so unlike most logic sims they dont need sorting into levels.
+pub struct CompiledSim {
+    kinds: Vec<Kind>,
+    input_start: Vec<u32>, // inputs of part i are inputs[input_start[i]..input_start[i + 1]]
+    inputs: Vec<u32>,
+    fanout_start: Vec<u32>, // same thing but for the parts each part drives
+    fanout: Vec<u32>,
+    timers: Vec<u32>,
+    cur: Vec<u64>,
+    prev: Vec<u64>,
+    check_all: bool, // nothing is known to be stable, ie on the first tick
+    dirty: Vec<u32>, // parts to evaluate next tick
+    dirty_marks: Vec<u64>,
+    updates: Vec<(u32, bool)>,
+    pub changed: Vec<u32>, // parts whose output changed in the last tick
+}
+
+impl CompiledSim {
+    pub fn new(state: &SimState) -> Self {
+        let count = state.part_types.len();
+        let kinds: Vec<Kind> = state
+            .part_types
+            .iter()
+            .zip(&state.part_inputs)
+            .map(|(part_type, inputs)| match part_type {
+                _ if inputs.is_empty() => Kind::Held,
+                PartType::And => Kind::And,
+                PartType::Or => Kind::Or,
+                PartType::Xor => Kind::Xor,
+                PartType::Nand => Kind::Nand,
+                PartType::Nor => Kind::Nor,
+                PartType::Xnor => Kind::Xnor,
+                PartType::Timer(_) => Kind::Timer,
+            })
+            .collect();
+
+        let mut input_start = Vec::with_capacity(count + 1);
+        let mut inputs = Vec::new();
+        let mut fanout_lists: Vec<Vec<u32>> = vec![Vec::new(); count];
+        for (i, part_inputs) in state.part_inputs.iter().enumerate() {
+            input_start.push(inputs.len() as u32);
+            for &input in part_inputs {
+                inputs.push(input as u32);
+                fanout_lists[input].push(i as u32);
+            }
+        }
+        input_start.push(inputs.len() as u32);
+        let mut fanout_start = Vec::with_capacity(count + 1);
+        let mut fanout = Vec::new();
+        for list in &mut fanout_lists {
+            list.dedup(); // the same wire can show up twice
+            fanout_start.push(fanout.len() as u32);
+            fanout.extend_from_slice(list);
+        }
+        fanout_start.push(fanout.len() as u32);
+
+        let timers = (0..count as u32)
+            .filter(|&i| kinds[i as usize] == Kind::Timer)
+            .collect();
+
+        let cur = pack(&state.part_outputs);
+        let prev = pack(&state.prev_outputs);
+        let changed = (0..count as u32)
+            .filter(|&i| get_bit(&cur, i as usize) != get_bit(&prev, i as usize))
+            .collect();
+        Self {
+            kinds,
+            input_start,
+            inputs,
+            fanout_start,
+            fanout,
+            timers,
+            dirty_marks: vec![0; cur.len()],
+            cur,
+            prev,
+            check_all: true,
+            dirty: Vec::new(),
+            updates: Vec::new(),
+            changed,
+        }
+    }
+
+    /// forces a parts output without rebuilding everything, see `SimState::set_output`
+    pub fn set_output(&mut self, i: usize, value: bool) {
+        set_bit(&mut self.cur, i, value);
+        set_bit(&mut self.prev, i, value);
+        // the part itself has to be looked at again too, gates with inputs go back to normal
+        let start = self.fanout_start[i] as usize;
+        let end = self.fanout_start[i + 1] as usize;
+        for target in std::iter::once(i as u32).chain(self.fanout[start..end].iter().copied()) {
+            if !get_bit(&self.dirty_marks, target as usize) {
+                set_bit(&mut self.dirty_marks, target as usize, true);
+                self.dirty.push(target);
+            }
+        }
+    }
+
+    fn part_inputs(&self, i: usize) -> &[u32] {
+        &self.inputs[self.input_start[i] as usize..self.input_start[i + 1] as usize]
+    }
+
+    fn evaluate(&self, kind: Kind, i: usize, part_types: &mut [PartType]) -> bool {
+        let cur = &self.cur;
+        let mut inputs = self
+            .part_inputs(i)
+            .iter()
+            .map(|&x| get_bit(cur, x as usize));
+        match kind {
+            Kind::And => inputs.all(|v| v),
+            Kind::Or => inputs.any(|v| v),
+            Kind::Xor => inputs.filter(|v| *v).count() % 2 == 1,
+            Kind::Nand => !inputs.all(|v| v),
+            Kind::Nor => !inputs.any(|v| v),
+            Kind::Xnor => inputs.filter(|v| *v).count() % 2 == 0,
+            Kind::Timer => {
+                let input = inputs.next().unwrap_or(false);
+                match &mut part_types[i] {
+                    PartType::Timer(buffer) => timer_step(buffer, input),
+                    _ => input,
+                }
+            }
+            Kind::Held => get_bit(&self.prev, i),
+        }
+    }
+
+    /// only evaluates parts whose inputs changed last tick, plus timers since their buffers move
+    /// every tick anyway
+    pub fn tick_event_driven(&mut self, part_types: &mut [PartType]) {
+        if self.check_all {
+            self.dirty = (0..self.kinds.len() as u32).collect();
+            self.check_all = false;
+        }
+        self.updates.clear();
+        for &i in &self.dirty {
+            let value = self.evaluate(self.kinds[i as usize], i as usize, part_types);
+            if value != get_bit(&self.cur, i as usize) {
+                self.updates.push((i, value));
+            }
+        }
+        // parts that changed last tick but not this one are now stable
+        for &i in &self.changed {
+            let value = get_bit(&self.cur, i as usize);
+            set_bit(&mut self.prev, i as usize, value);
+        }
+        self.changed.clear();
+        for &(i, value) in &self.updates {
+            set_bit(&mut self.prev, i as usize, !value);
+            set_bit(&mut self.cur, i as usize, value);
+            self.changed.push(i);
+        }
+
+        self.dirty.clear();
+        self.dirty_marks.fill(0);
+        let mut mark = |i: u32, dirty: &mut Vec<u32>| {
+            if !get_bit(&self.dirty_marks, i as usize) {
+                set_bit(&mut self.dirty_marks, i as usize, true);
+                dirty.push(i);
+            }
+        };
+        for &i in &self.changed {
+            let start = self.fanout_start[i as usize] as usize;
+            let end = self.fanout_start[i as usize + 1] as usize;
+            for &target in &self.fanout[start..end] {
+                mark(target, &mut self.dirty);
+            }
+            // a held part that changed goes back to its old value next tick, like the simple
+            // engine swapping its buffers
+            if self.kinds[i as usize] == Kind::Held {
+                mark(i, &mut self.dirty);
+            }
+        }
+        for &i in &self.timers {
+            mark(i, &mut self.dirty);
+        }
+    }
+}
+
+/// moves a timers buffer along one tick, returns its new output
+pub fn timer_step(buffer: &mut VecDeque<bool>, input: bool) -> bool {
+    if buffer.is_empty() {
+        // 0 tick timer doesnt need any buffer stuff, just pass it through
+        input
+    } else {
+        let out = buffer.pop_back().unwrap_or(false);
+        buffer.push_front(input);
+        out
+    }
+}
+
+impl SimState {
+    /// ticks with the event driven engine, then copies over only the outputs that changed
+    pub(crate) fn tick_compiled(&mut self) {
+        if self.compiled.is_none() {
+            self.compiled = Some(Box::new(CompiledSim::new(self)));
+        }
+        let Some(compiled) = &mut self.compiled else {
+            return;
+        };
+        // parts that changed the tick before are stable now, so prev catches up to them
+        for &i in &compiled.changed {
+            self.prev_outputs[i as usize] = self.part_outputs[i as usize];
+        }
+        compiled.tick_event_driven(&mut self.part_types);
+        for &i in &compiled.changed {
+            let i = i as usize;
+            self.prev_outputs[i] = self.part_outputs[i];
+            self.part_outputs[i] = !self.part_outputs[i];
+        }
+        self.tick += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canvas::CanvasSnapshot;
+    use crate::headless::{HeadlessSim, InputVector, benchmark};
+    use std::path::PathBuf;
+
+    fn load(project: &str, module: &str) -> CanvasSnapshot {
+        let project = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../sample_projects")
+            .join(project);
+        let mut errors = Vec::new();
+        CanvasSnapshot::load(project.join(module), Some(project), &mut errors, Vec::new()).unwrap()
+    }
+
+    /// flips the inputs around in a way that doesnt repeat too quickly
+    fn toggles(canvas: &CanvasSnapshot, ticks: u64) -> Vec<InputVector> {
+        let inputs = HeadlessSim::new(canvas).inputs;
+        let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut vectors = Vec::new();
+        for tick in (0..ticks).step_by(3) {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            let (label, _) = &inputs[seed as usize % inputs.len()];
+            vectors.push(InputVector {
+                tick,
+                label: label.clone(),
+                value: seed & 1 == 1,
+            });
+        }
+        vectors
+    }
+
+    #[test]
+    fn engines_agree() {
+        for (project, module) in [("test", "semantics.sml"), ("6502", "main_cpu.sml")] {
+            let canvas = load(project, module);
+            let vectors = toggles(&canvas, 300);
+            for result in benchmark(&canvas, &vectors, 300, 1, 0).unwrap() {
+                assert_eq!(
+                    result.first_mismatch,
+                    None,
+                    "{} on {}",
+                    result.engine.to_label(),
+                    module
+                );
+            }
+        }
+    }
+}
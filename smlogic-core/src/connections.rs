@@ -0,0 +1,58 @@
+use crate::parts::Port;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+#[derive(Clone, Deserialize, Serialize, PartialEq)]
+pub struct Connection {
+    pub start: Port,
+    pub end: Port,
+    #[serde(skip)]
+    pub simulation_index: Option<usize>,
+}
+
+/// how many wires away from something with no inputs every part is, `wires` being where each
+/// parts output goes. loops nothing drives start from whichever of their parts comes first in
+/// `parts`, anything not in `parts` is left at 0
+pub fn connection_depth(wires: &[Vec<usize>], parts: &[usize]) -> Vec<usize> {
+    let mut has_inputs = vec![false; wires.len()];
+    for &target in wires.iter().flatten() {
+        has_inputs[target] = true;
+    }
+    let mut depth: Vec<Option<usize>> = vec![None; wires.len()];
+    let mut seeds = parts
+        .iter()
+        .filter(|&&i| !has_inputs[i])
+        .chain(parts.iter())
+        .copied();
+    let mut queue: VecDeque<usize> = VecDeque::new();
+    loop {
+        while let Some(i) = queue.pop_front() {
+            let next = depth[i].unwrap_or(0) + 1;
+            for &target in &wires[i] {
+                if depth[target].is_none() {
+                    depth[target] = Some(next);
+                    queue.push_back(target);
+                }
+            }
+        }
+        let Some(seed) = seeds.find(|&i| depth[i].is_none()) else {
+            break;
+        };
+        depth[seed] = Some(0);
+        queue.push_back(seed);
+    }
+    depth.into_iter().map(|d| d.unwrap_or(0)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn depth_counts_wires_from_undriven_parts() {
+        // 0 -> 1 -> 2, 3 <-> 4 is a loop nothing drives, 5 isnt a part
+        let wires = vec![vec![1], vec![2], vec![], vec![4], vec![3], vec![0]];
+        let depth = connection_depth(&wires, &[0, 1, 2, 3, 4]);
+        assert_eq!(depth, vec![0, 1, 2, 0, 1, 0]);
+    }
+}
@@ -0,0 +1,165 @@
+use std::fmt;
+use std::path::PathBuf;
+
+/// anything that can go wrong loading or saving a module
+#[derive(Debug)]
+pub enum ModuleError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    /// module that ends up containing itself
+    Recursive(PathBuf),
+    /// a module part failed to load, the canvas around it still loads fine but the module is
+    /// marked as problematic
+    Module {
+        path: String,
+        chain: Option<String>, // the modules its nested inside of, outermost first
+        source: Box<ModuleError>,
+    },
+}
+
+impl fmt::Display for ModuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Json(e) => write!(f, "{}", e),
+            Self::Recursive(path) => write!(
+                f,
+                "Cant put a module inside of itself! ({})",
+                path.to_string_lossy()
+            ),
+            Self::Module {
+                path,
+                chain: Some(chain),
+                source,
+            } => write!(
+                f,
+                "Failed to load module {} (in {}): {}",
+                path, chain, source
+            ),
+            Self::Module {
+                path,
+                chain: None,
+                source,
+            } => write!(f, "Failed to load module {}: {}", path, source),
+        }
+    }
+}
+
+impl std::error::Error for ModuleError {}
+
+impl From<std::io::Error> for ModuleError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for ModuleError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
+/// anything that can stop a blueprint from being exported
+#[derive(Debug)]
+pub enum ExportError {
+    NoBlueprintFolder,
+    InvalidMaxSize,
+    NoName,
+    NoNewName,
+    NoUuid,
+    BlueprintNotFound,
+    IconRead(std::io::Error),
+    InvalidIcon,
+    Write(&'static str),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoBlueprintFolder => write!(f, "No Blueprint folder set! (check settings)"),
+            Self::InvalidMaxSize => write!(f, "cant set max size <= 0!"),
+            Self::NoName => write!(f, "no name supplied?"),
+            Self::NoNewName => write!(f, "new blueprints need a name!"),
+            Self::NoUuid => write!(f, "no uuid supplied?"),
+            Self::BlueprintNotFound => write!(f, "couldn't find blueprint!"),
+            Self::IconRead(e) => write!(f, "failed to read supplied icon! ({})", e),
+            Self::InvalidIcon => write!(f, "icon must be a 128x128 png!"),
+            Self::Write(file) => write!(f, "Failed to write {}!", file),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+/// anything that can stop a blueprint from being imported
+#[derive(Debug)]
+pub enum ImportError {
+    Read(std::io::Error),
+    Json(serde_json::Error),
+    NoLogic,
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read(e) => write!(f, "failed to read blueprint.json! ({})", e),
+            Self::Json(e) => write!(f, "invalid blueprint.json! ({})", e),
+            Self::NoLogic => write!(f, "blueprint doesnt have any logic in it!"),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+/// problems with an input vector file or the inputs it drives
+#[derive(Debug)]
+pub enum VectorError {
+    Parse { line: usize, message: String },
+    UnknownInput(String),
+    UnknownOutput(String),
+}
+
+impl fmt::Display for VectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            Self::UnknownInput(label) => write!(f, "no input labeled \"{}\"", label),
+            Self::UnknownOutput(label) => write!(f, "no output labeled \"{}\"", label),
+        }
+    }
+}
+
+impl std::error::Error for VectorError {}
+
+/// problems saving or loading a simulation state file
+#[derive(Debug)]
+pub enum StateError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    /// none of the saved parts exist in this module, probably saved from a different one
+    NoMatchingParts,
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Json(e) => write!(f, "{}", e),
+            Self::NoMatchingParts => write!(f, "none of the saved parts are in this module"),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+impl From<std::io::Error> for StateError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for StateError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
@@ -0,0 +1,1201 @@
+use crate::{
+    canvas::CanvasSnapshot,
+    connections::connection_depth,
+    error::ExportError,
+    savestate::{SaveState, path_key},
+    simulator::{PartType, RawCanvasData, get_canvas_raw_data},
+};
+
+use ecolor::Color32;
+use emath::Pos2;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+const DEFAULT_ICON: &[u8] = include_bytes!("../../assets/default_icon.png");
+
+pub const GATE_SHAPE_ID: &str = "9f0f56e8-2c31-4d83-996c-d00a9b296c3f";
+pub const TIMER_SHAPE_ID: &str = "8f7fd0e7-c46e-4944-a414-7ce2437bb30f";
+const GLASS_SHAPE_ID: &str = "5f41af56-df4c-4837-9b3c-10781335757f";
+const GLASS_COLOR: &str = "E4F8FF";
+const LAYOUT_FILE: &str = "smlogic_layout.json";
+
+pub type BlockPos = (i32, i32, i32); // x, y, z in blocks, z is up
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ExportType {
+    FromName,
+    FromUUID,
+    New,
+}
+impl ExportType {
+    pub const TYPES: &[Self] = &[Self::FromName, Self::FromUUID, Self::New];
+
+    pub fn to_label(&self) -> &'static str {
+        match self {
+            Self::FromName => "Overwrite by name",
+            Self::FromUUID => "Overwrite by UUID",
+            Self::New => "New blueprint",
+        }
+    }
+}
+
+/// what order gates fill up the block in. all but canvas fill the same x/y/z grid so the size
+/// limits still apply, they just change which gate ends up next to which
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub enum Placement {
+    #[default]
+    Raster, // the order parts are in the canvas
+    Modules, // every module instance gets its own run of blocks, ordered by depth inside it
+    Depth,   // by how many gates away from an input each gate is, so signals flow along the grid
+    Canvas,  // where they are on the canvas, see `canvas_layout`. ignores the size limits
+}
+
+impl Placement {
+    pub const PLACEMENTS: &[Self] = &[Self::Raster, Self::Modules, Self::Depth, Self::Canvas];
+
+    pub fn to_label(&self) -> &'static str {
+        match self {
+            Self::Raster => "Raster",
+            Self::Modules => "By module",
+            Self::Depth => "By depth",
+            Self::Canvas => "Canvas layout",
+        }
+    }
+
+    /// the non io parts in the order they should be placed, `wires` is where each parts output
+    /// goes
+    fn order(
+        &self,
+        io_parts: &HashSet<usize>,
+        part_paths: &[Vec<u64>],
+        wires: &[Vec<usize>],
+    ) -> Vec<usize> {
+        let total = part_paths.len();
+        let mut gates: Vec<usize> = (0..total).filter(|i| !io_parts.contains(i)).collect();
+        let depth = || connection_depth(wires, &(0..total).collect::<Vec<_>>());
+        match self {
+            Self::Raster | Self::Canvas => {}
+            Self::Modules => {
+                let depth = depth();
+                gates.sort_by_key(|&i| {
+                    let path = &part_paths[i];
+                    (&path[..path.len().saturating_sub(1)], depth[i], i)
+                });
+            }
+            Self::Depth => {
+                let depth = depth();
+                gates.sort_by_key(|&i| (depth[i], i));
+            }
+        }
+        gates
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExporterSettings {
+    pub maintain_io_position: bool,
+    pub io_x_scale: f32, // if maintiain io position is true, scales down canvas positions to SM ones
+    pub io_y_scale: f32,
+    pub max_x: Option<usize>,
+    pub max_y: Option<usize>,
+    pub max_z: Option<usize>,
+    pub export_type: ExportType,
+    pub identifier: Option<String>,
+    pub new_name: Option<String>,
+    pub new_desc: Option<String>,
+    pub new_icon: Option<std::path::PathBuf>,
+    #[serde(default)]
+    pub keep_positions: bool, // reuse where parts were put last export to this blueprint
+    #[serde(default)]
+    pub placement: Placement,
+}
+
+impl ExporterSettings {
+    pub const NEW: Self = Self {
+        maintain_io_position: false,
+        io_x_scale: 1.0 / 80.0,
+        io_y_scale: 1.0 / 60.0,
+        max_x: None,
+        max_y: None,
+        max_z: None,
+        export_type: ExportType::FromName,
+        identifier: None,
+        new_name: None,
+        new_desc: None,
+        new_icon: None,
+        keep_positions: false,
+        placement: Placement::Raster,
+    };
+}
+
+impl Default for ExporterSettings {
+    fn default() -> Self {
+        Self::NEW
+    }
+}
+
+/// finds (or creates) the blueprint the settings point at, and applies the optional
+/// name/description/icon overrides. shared by the gui exporter and the command line.
+pub fn resolve_blueprint(
+    exporter_settings: &ExporterSettings,
+    bp_folder: PathBuf,
+) -> Result<BluePrint, ExportError> {
+    if [
+        exporter_settings.max_x,
+        exporter_settings.max_y,
+        exporter_settings.max_z,
+    ]
+    .contains(&Some(0))
+    {
+        return Err(ExportError::InvalidMaxSize);
+    }
+    let mut bp = match exporter_settings.export_type {
+        ExportType::FromName => {
+            let Some(ref name) = exporter_settings.identifier else {
+                return Err(ExportError::NoName);
+            };
+            BluePrint::from_name(name, &bp_folder).ok_or(ExportError::BlueprintNotFound)?
+        }
+        ExportType::FromUUID => {
+            let Some(ref uuid) = exporter_settings.identifier else {
+                return Err(ExportError::NoUuid);
+            };
+            BluePrint::from_uuid(uuid.clone(), bp_folder).ok_or(ExportError::BlueprintNotFound)?
+        }
+        ExportType::New => {
+            let name = match exporter_settings.new_name {
+                Some(ref name) if !name.is_empty() => name,
+                _ => return Err(ExportError::NoNewName),
+            };
+            let icon = match exporter_settings.new_icon {
+                Some(ref icon_path) => Some(read_icon(icon_path)?),
+                None => None,
+            };
+            BluePrint::new(
+                name.clone(),
+                exporter_settings.new_desc.clone().unwrap_or_default(),
+                bp_folder,
+                icon,
+            )
+        }
+    };
+
+    if bp.description.is_empty() {
+        bp.description = "#{STEAM_WORKSHOP_NO_DESCRIPTION}".to_string();
+    }
+    if matches!(
+        exporter_settings.export_type,
+        ExportType::FromName | ExportType::FromUUID
+    ) {
+        if let Some(ref name) = exporter_settings.new_name {
+            bp.name = name.clone()
+        }
+        if let Some(ref desc) = exporter_settings.new_desc {
+            bp.description = desc.clone()
+        }
+        if let Some(ref icon_path) = exporter_settings.new_icon {
+            bp.icon = Some(read_icon(icon_path)?);
+        }
+    }
+    Ok(bp)
+}
+
+fn read_icon(icon_path: &Path) -> Result<Vec<u8>, ExportError> {
+    let icon_data = fs::read(icon_path).map_err(ExportError::IconRead)?;
+    if !is_128x128_png(&icon_data) {
+        return Err(ExportError::InvalidIcon);
+    }
+    Ok(icon_data)
+}
+
+/// what the layout file remembers the exported module as, its full path. part paths are only
+/// unique inside one module, so a layout is never used for a different one. unsaved canvases
+/// get an empty source and never reuse a layout
+pub fn layout_source(module_path: Option<&Path>) -> String {
+    module_path
+        .map(|path| {
+            path.canonicalize()
+                .unwrap_or(path.to_path_buf())
+                .to_string_lossy()
+                .into_owned()
+        })
+        .unwrap_or_default()
+}
+
+/// where every part went last time a blueprint was exported, by part path (see
+/// `savestate::path_key`). saved next to the blueprint so re-exporting after a small change
+/// doesnt move everything around in game
+#[derive(Serialize, Deserialize, Default)]
+struct ExportLayout {
+    #[serde(default)]
+    source: String, // see `layout_source`
+    parts: BTreeMap<String, BlockPos>,
+}
+
+impl ExportLayout {
+    /// the layout from the last export, only if it was exported from `source`
+    fn load(blueprint_path: &Path, source: &str) -> Option<Self> {
+        if source.is_empty() {
+            return None;
+        }
+        let contents = fs::read_to_string(blueprint_path.join(LAYOUT_FILE)).ok()?;
+        let layout: Self = serde_json::from_str(&contents).ok()?;
+        (layout.source == source).then_some(layout)
+    }
+
+    /// puts every part that was exported before back where it was, except the ones in `skip`
+    /// which keep the spot they were given. new parts keep theirs too unless something is
+    /// already there, then they get the closest free block inside the area the fresh layout
+    /// took up (the io plane for io parts). returns how many parts went back where they were
+    fn apply(
+        &self,
+        positioning: &mut [BlockPos],
+        keys: &[String],
+        skip: &HashSet<usize>,
+        io_parts: &HashSet<usize>,
+    ) -> usize {
+        let area = |io: bool| {
+            let cells = (0..keys.len()).filter(|i| io_parts.contains(i) == io);
+            let low = cells
+                .clone()
+                .map(|i| positioning[i])
+                .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.min(b.2)));
+            let high = cells
+                .map(|i| positioning[i])
+                .reduce(|a, b| (a.0.max(b.0), a.1.max(b.1), a.2.max(b.2)));
+            low.zip(high).unwrap_or(((0, 0, 0), (0, 0, 0)))
+        };
+        let (io_area, gate_area) = (area(true), area(false));
+        let mut taken: HashSet<BlockPos> = skip.iter().map(|&i| positioning[i]).collect();
+        let mut new_parts = Vec::new();
+        for (i, key) in keys.iter().enumerate() {
+            match self.parts.get(key) {
+                _ if skip.contains(&i) => {}
+                Some(&pos) => {
+                    positioning[i] = pos;
+                    taken.insert(pos);
+                }
+                None => new_parts.push(i),
+            }
+        }
+        let kept = keys.len() - skip.len() - new_parts.len();
+        for i in new_parts {
+            let area = if io_parts.contains(&i) {
+                io_area
+            } else {
+                gate_area
+            };
+            let (pos, _) = nearest_free(&taken, positioning[i], 0, area);
+            positioning[i] = pos;
+            taken.insert(pos);
+        }
+        kept
+    }
+}
+
+/// where every part goes in the blueprint, `compute_positions` in the order the placement
+/// strategy picks (or `canvas_layout`) plus whatever the last export of the same module to
+/// `blueprint_path` left behind if the settings say to keep it. also returns how many parts
+/// that layout put back
+fn place_parts(
+    positions: &[Pos2],
+    top_level: &HashMap<u64, Pos2>, // canvas positions of the top level parts, modules included
+    io_parts: &HashSet<usize>,
+    part_paths: &[Vec<u64>],
+    wires: &[Vec<usize>], // where each parts output goes
+    exporter_settings: &ExporterSettings,
+    previous: Option<(&Path, &str)>, // blueprint path and layout source
+) -> (Vec<BlockPos>, usize) {
+    let total = part_paths.len();
+    let canvas = exporter_settings.placement == Placement::Canvas;
+    let mut positioning = if canvas {
+        // parts inside modules go where their module is on the canvas
+        let canvas_positions: Vec<Pos2> = part_paths
+            .iter()
+            .enumerate()
+            .map(|(i, path)| top_level.get(&path[0]).copied().unwrap_or(positions[i]))
+            .collect();
+        canvas_layout(io_parts, part_paths, &canvas_positions, exporter_settings)
+    } else {
+        compute_positions(total, io_parts, positions, exporter_settings)
+    };
+    if !canvas && exporter_settings.placement != Placement::Raster {
+        // compute_positions hands out grid cells in index order, so give them out again in
+        // the order we actually want
+        let cells: Vec<BlockPos> = (0..total)
+            .filter(|i| !io_parts.contains(i))
+            .map(|i| positioning[i])
+            .collect();
+        let order = exporter_settings
+            .placement
+            .order(io_parts, part_paths, wires);
+        for (i, cell) in order.into_iter().zip(cells) {
+            positioning[i] = cell;
+        }
+    }
+    if exporter_settings.keep_positions
+        && let Some(layout) = previous.and_then(|(path, source)| ExportLayout::load(path, source))
+    {
+        // io parts placed from the canvas stay where the canvas says
+        let skip = if exporter_settings.maintain_io_position || canvas {
+            io_parts.clone()
+        } else {
+            HashSet::new()
+        };
+        let keys: Vec<String> = part_paths.iter().map(|path| path_key(path)).collect();
+        let kept = layout.apply(&mut positioning, &keys, &skip, io_parts);
+        return (positioning, kept);
+    }
+    (positioning, 0)
+}
+
+/// every part where it is on the canvas, scaled down the same way io parts are. io parts go on
+/// the io plane like always and everything else goes right behind it, anything that lands on a
+/// block thats already taken moves to the closest free one. top level parts go first so they
+/// get their exact spot, parts inside modules spread out from where the module is
+fn canvas_layout(
+    io_parts: &HashSet<usize>,
+    part_paths: &[Vec<u64>],
+    canvas_positions: &[Pos2],
+    settings: &ExporterSettings,
+) -> Vec<BlockPos> {
+    let projected: Vec<(i32, i32)> = canvas_positions
+        .iter()
+        .map(|pos| {
+            (
+                -(pos.x * settings.io_x_scale).round() as i32,
+                -(pos.y * settings.io_y_scale).round() as i32,
+            )
+        })
+        .collect();
+    // same corner as compute_positions uses for io, y=1 z=0
+    let min_y = projected.iter().map(|p| p.0).min().unwrap_or(0);
+    let min_z = projected.iter().map(|p| p.1).min().unwrap_or(0);
+
+    let mut order: Vec<usize> = (0..part_paths.len()).collect();
+    order.sort_by_key(|&i| (part_paths[i].len(), i));
+    let mut out = vec![(0, 0, 0); part_paths.len()];
+    let mut taken: HashSet<BlockPos> = HashSet::new();
+    let mut full_shells: HashMap<BlockPos, i32> = HashMap::new();
+    for i in order {
+        let (y, z) = projected[i];
+        let io = io_parts.contains(&i);
+        let start = (if io { -1 } else { 0 }, y - min_y + 1, z - min_z);
+        let radius = full_shells.get(&start).copied().unwrap_or(0);
+        // io parts stay on the io plane, everything else stays behind it
+        let area = if io {
+            ((-1, i32::MIN, i32::MIN), (-1, i32::MAX, i32::MAX))
+        } else {
+            ((0, i32::MIN, i32::MIN), (i32::MAX, i32::MAX, i32::MAX))
+        };
+        let (pos, radius) = nearest_free(&taken, start, radius, area);
+        full_shells.insert(start, radius);
+        taken.insert(pos);
+        out[i] = pos;
+    }
+    out
+}
+
+/// closest block to `start` thats not taken and inside `area` (its lowest and highest
+/// corner), looking in shells of blocks `radius` or more away. if the whole area is taken it
+/// looks outside of it instead
+fn nearest_free(
+    taken: &HashSet<BlockPos>,
+    start: BlockPos,
+    mut radius: i32,
+    (low, high): (BlockPos, BlockPos),
+) -> (BlockPos, i32) {
+    // how far along one axis it can go each way without leaving the area
+    let steps = |start: i32, low: i32, high: i32| {
+        (
+            low.saturating_sub(start).min(0),
+            high.saturating_sub(start).max(0),
+        )
+    };
+    let (x, y, z) = (
+        steps(start.0, low.0, high.0),
+        steps(start.1, low.1, high.1),
+        steps(start.2, low.2, high.2),
+    );
+    let furthest = [x.0, x.1, y.0, y.1, z.0, z.1]
+        .iter()
+        .map(|d| d.saturating_abs())
+        .max()
+        .unwrap_or(0);
+    loop {
+        let mut best: Option<(i64, BlockPos)> = None;
+        for dx in x.0.max(-radius)..=x.1.min(radius) {
+            for dy in y.0.max(-radius)..=y.1.min(radius) {
+                for dz in z.0.max(-radius)..=z.1.min(radius) {
+                    if dx.abs().max(dy.abs()).max(dz.abs()) != radius {
+                        continue;
+                    }
+                    let pos = (start.0 + dx, start.1 + dy, start.2 + dz);
+                    let distance = [dx, dy, dz].iter().map(|&d| d as i64 * d as i64).sum();
+                    if !taken.contains(&pos) && best.is_none_or(|(d, _)| distance < d) {
+                        best = Some((distance, pos));
+                    }
+                }
+            }
+        }
+        if let Some((_, pos)) = best {
+            return (pos, radius);
+        }
+        if radius >= furthest {
+            let everywhere = (
+                (i32::MIN, i32::MIN, i32::MIN),
+                (i32::MAX, i32::MAX, i32::MAX),
+            );
+            return nearest_free(taken, start, radius + 1, everywhere);
+        }
+        radius += 1;
+    }
+}
+
+fn top_level_positions(canvas: &CanvasSnapshot) -> HashMap<u64, Pos2> {
+    canvas
+        .parts
+        .iter()
+        .map(|(id, part)| (*id, part.pos))
+        .collect()
+}
+
+/// position and size of the glass behind the io parts, if there are any
+fn glass_backing(
+    positioning: &[BlockPos],
+    io_parts: &HashSet<usize>,
+) -> Option<(BlockPos, BlockPos)> {
+    let min_y = io_parts.iter().map(|&i| positioning[i].1).min()?;
+    let max_y = io_parts.iter().map(|&i| positioning[i].1).max()?;
+    let min_z = io_parts.iter().map(|&i| positioning[i].2).min()?;
+    let max_z = io_parts.iter().map(|&i| positioning[i].2).max()?;
+    Some((
+        (-1, min_y - 1, min_z),
+        (1, max_y - min_y + 1, max_z - min_z + 1),
+    ))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum BlockKind {
+    Gate,
+    Timer,
+    IO, // io and important parts, facing out of the glass
+    Glass,
+}
+
+#[derive(Clone)]
+pub struct PreviewBlock {
+    pub cell: BlockPos, // the block the part takes up in game, not its "pos"
+    pub color: Color32,
+    pub kind: BlockKind,
+}
+
+/// everything an export would place, without writing anything. io parts are rotated so their
+/// "pos" isnt the block they take up, the cells here are the ones you would see in game
+#[derive(Clone, Default)]
+pub struct ExportPreview {
+    pub blocks: Vec<PreviewBlock>,
+    pub kept: usize, // parts put back where the last export had them, see `keep_positions`
+}
+
+impl ExportPreview {
+    pub fn count(&self, kind: BlockKind) -> usize {
+        self.blocks.iter().filter(|b| b.kind == kind).count()
+    }
+
+    /// smallest and biggest cell used, both inclusive
+    pub fn bounds(&self) -> Option<(BlockPos, BlockPos)> {
+        let first = self.blocks.first()?.cell;
+        Some(self.blocks.iter().fold((first, first), |(min, max), b| {
+            let c = b.cell;
+            (
+                (min.0.min(c.0), min.1.min(c.1), min.2.min(c.2)),
+                (max.0.max(c.0), max.1.max(c.1), max.2.max(c.2)),
+            )
+        }))
+    }
+
+    /// width, depth and height in blocks
+    pub fn size(&self) -> BlockPos {
+        match self.bounds() {
+            Some((min, max)) => (max.0 - min.0 + 1, max.1 - min.1 + 1, max.2 - min.2 + 1),
+            None => (0, 0, 0),
+        }
+    }
+}
+
+/// the flattened canvas a preview is laid out from. flattening is the slow part, so this is
+/// built once and reused for as long as only the exporter settings change
+pub struct PreviewData {
+    top_level: HashMap<u64, Pos2>,
+    parts: Vec<PartType>,
+    colors: Vec<Color32>,
+    positions: Vec<Pos2>,
+    wires: Vec<Vec<usize>>,   // where each parts output goes
+    io_parts: HashSet<usize>, // important parts included, they get placed the same way
+    part_paths: Vec<Vec<u64>>,
+}
+
+impl PreviewData {
+    pub fn new(canvas: CanvasSnapshot) -> Self {
+        let top_level = top_level_positions(&canvas);
+        let RawCanvasData {
+            parts,
+            colors,
+            positions,
+            connections,
+            io_parts,
+            important_parts,
+            part_paths,
+            ..
+        } = get_canvas_raw_data(canvas, true, &Vec::new());
+        let mut wires: Vec<Vec<usize>> = vec![Vec::new(); parts.len()];
+        for (from, to) in connections {
+            wires[from].push(to);
+        }
+        Self {
+            top_level,
+            parts,
+            colors,
+            positions,
+            wires,
+            io_parts: io_parts.into_iter().chain(important_parts).collect(),
+            part_paths,
+        }
+    }
+}
+
+/// lays the canvas out the same way `BluePrint::export` would, `blueprint_path` and `source`
+/// are only used to find the layout from the last export when keeping positions
+pub fn preview_export(
+    data: &PreviewData,
+    exporter_settings: &ExporterSettings,
+    blueprint_path: Option<&Path>,
+    source: &str,
+) -> ExportPreview {
+    let PreviewData {
+        top_level,
+        parts,
+        colors,
+        positions,
+        wires,
+        io_parts,
+        part_paths,
+    } = data;
+    let (positioning, kept) = place_parts(
+        positions,
+        top_level,
+        io_parts,
+        part_paths,
+        wires,
+        exporter_settings,
+        blueprint_path.map(|path| (path, source)),
+    );
+
+    let mut blocks = Vec::with_capacity(parts.len());
+    for (i, part_type) in parts.iter().enumerate() {
+        let (x, y, z) = positioning[i];
+        let (cell, kind) = if io_parts.contains(&i) {
+            // xaxis 3 zaxis -2 points the block back along x and y
+            ((x - 1, y - 1, z), BlockKind::IO)
+        } else if matches!(part_type, PartType::Timer(_)) {
+            ((x, y, z), BlockKind::Timer)
+        } else {
+            ((x, y, z), BlockKind::Gate)
+        };
+        blocks.push(PreviewBlock {
+            cell,
+            color: colors[i],
+            kind,
+        });
+    }
+    if let Some(((x, y, z), (size_x, size_y, size_z))) = glass_backing(&positioning, io_parts) {
+        let color = Color32::from_hex(&format!("#{}", GLASS_COLOR)).unwrap_or(Color32::WHITE);
+        for gx in x..x + size_x {
+            for gy in y..y + size_y {
+                for gz in z..z + size_z {
+                    blocks.push(PreviewBlock {
+                        cell: (gx, gy, gz),
+                        color,
+                        kind: BlockKind::Glass,
+                    });
+                }
+            }
+        }
+    }
+    ExportPreview { blocks, kept }
+}
+
+// this function was ai generated, didnt feel like doing all that math myself
+fn compute_positions(
+    total: usize,
+    io_set: &HashSet<usize>,
+    canvas_positions: &[Pos2],
+    settings: &ExporterSettings,
+) -> Vec<(i32, i32, i32)> {
+    let io_indices = io_set;
+    let non_io_count = total - io_indices.len();
+    let io_count = io_indices.len();
+
+    let n = non_io_count as f32;
+    let ceil_div = |a: f32, b: f32| (a / b).ceil() as usize;
+    let (eff_x, eff_y, eff_z) = match (settings.max_x, settings.max_y, settings.max_z) {
+        (Some(x), Some(y), Some(z)) => (x.max(1), y.max(1), z.max(1)),
+        (Some(x), Some(y), None) => (x.max(1), y.max(1), ceil_div(n, (x * y) as f32).max(1)),
+        (Some(x), None, Some(z)) => (x.max(1), ceil_div(n, (x * z) as f32).max(1), z.max(1)),
+        (None, Some(y), Some(z)) => (ceil_div(n, (y * z) as f32).max(1), y.max(1), z.max(1)),
+        (Some(x), None, None) => {
+            let s = (n / x as f32).ceil().sqrt().ceil() as usize;
+            (x.max(1), s.max(1), s.max(1))
+        }
+        (None, Some(y), None) => {
+            let s = (n / y as f32).ceil().sqrt().ceil() as usize;
+            (s.max(1), y.max(1), s.max(1))
+        }
+        (None, None, Some(z)) => {
+            let s = (n / z as f32).ceil().sqrt().ceil() as usize;
+            (s.max(1), s.max(1), z.max(1))
+        }
+        (None, None, None) => {
+            let s = n.cbrt().ceil() as usize;
+            (s.max(1), s.max(1), s.max(1))
+        }
+    };
+
+    let io_side = (io_count as f32).sqrt().ceil() as usize;
+
+    let mut out = vec![(0i32, 0i32, 0i32); total];
+    let mut gate_counter = 0;
+    let mut io_counter = 0;
+
+    for i in 0..total {
+        out[i] = if io_set.contains(&i) {
+            let pos = if settings.maintain_io_position {
+                (
+                    -1,
+                    -(canvas_positions[i].x * settings.io_x_scale).round() as i32,
+                    -(canvas_positions[i].y * settings.io_y_scale).round() as i32,
+                )
+            } else {
+                let side = io_side.max(1);
+                (
+                    -1,
+                    -((io_counter % side) as i32),
+                    -((io_counter / side) as i32),
+                )
+            };
+            io_counter += 1;
+            pos
+        } else {
+            let x = (gate_counter % eff_x) as i32;
+            let y = ((gate_counter / eff_x) % eff_y) as i32;
+            let z = ((gate_counter / (eff_x * eff_y)) % eff_z) as i32;
+            gate_counter += 1;
+            (x, y, z)
+        };
+    }
+
+    // normalize IO parts so the corner lines up with y=1, z=0
+    if let (Some(&min_y), Some(&min_z)) = (
+        io_indices
+            .iter()
+            .map(|&i| out[i].1)
+            .collect::<Vec<_>>()
+            .iter()
+            .min(),
+        io_indices
+            .iter()
+            .map(|&i| out[i].2)
+            .collect::<Vec<_>>()
+            .iter()
+            .min(),
+    ) {
+        for &i in io_indices {
+            out[i].1 = out[i].1 - min_y + 1;
+            out[i].2 -= min_z;
+        }
+    }
+
+    out
+}
+
+fn get_user_folder() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    let base = PathBuf::from(std::env::var("APPDATA").ok()?);
+
+    #[cfg(not(target_os = "windows"))]
+    let base = dirs::home_dir()?.join(
+        ".steam/steam/steamapps/compatdata/387990/pfx/drive_c/users/steamuser/Application Data",
+    ); // untested :P
+
+    let folder = base.join("Axolot Games/Scrap Mechanic/User/");
+    folder.is_dir().then_some(folder)
+}
+
+pub fn get_bp_folder() -> Option<PathBuf> {
+    let user_folder = get_user_folder()?;
+    let users: Vec<_> = fs::read_dir(&user_folder)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_dir())
+        .collect();
+
+    if users.len() == 1 {
+        Some(users[0].path().join("Blueprints/"))
+    } else {
+        None // more than one user, cant auto-detect (not sure why there would be multiple anyway??)
+    }
+}
+
+/// (name, uuid) of every blueprint in the folder, sorted by name
+pub fn list_blueprints(folder: &Path) -> Vec<(String, String)> {
+    let Ok(entries) = fs::read_dir(folder) else {
+        return Vec::new();
+    };
+    let mut blueprints: Vec<(String, String)> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().join("blueprint.json").is_file())
+        .filter_map(|e| {
+            let contents = fs::read_to_string(e.path().join("description.json")).ok()?;
+            let json = serde_json::from_str::<Value>(&contents).ok()?;
+            let name = json["name"].as_str()?.to_string();
+            Some((name, e.file_name().to_string_lossy().into_owned()))
+        })
+        .collect();
+    blueprints.sort_by_key(|(name, _)| name.to_lowercase());
+    blueprints
+}
+
+fn get_id_from_name(name: &str, folder: &Path) -> Option<(String, PathBuf, Value)> {
+    for entry in fs::read_dir(folder).ok()? {
+        let Ok(entry) = entry else {
+            continue;
+        };
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+
+        if let Ok(contents) = fs::read_to_string(path.join("description.json"))
+            && let Ok(json) = serde_json::from_str::<Value>(&contents)
+            && json["name"].as_str() == Some(name)
+        {
+            return Some((entry.file_name().to_string_lossy().into_owned(), path, json));
+        }
+    }
+    None
+}
+
+fn is_128x128_png(data: &[u8]) -> bool {
+    if data.len() < 24 {
+        return false;
+    }
+    let width = u32::from_be_bytes([data[16], data[17], data[18], data[19]]);
+    let height = u32::from_be_bytes([data[20], data[21], data[22], data[23]]);
+    width == 128 && height == 128
+}
+
+pub struct BluePrint {
+    pub name: String,
+    pub description: String,
+    pub uuid: String,
+    pub path: PathBuf,
+    pub icon: Option<Vec<u8>>,
+}
+
+impl BluePrint {
+    pub fn from_name(name: &str, folder: &Path) -> Option<Self> {
+        if let Some((uuid, path, json)) = get_id_from_name(name, folder) {
+            let name = &json["name"].as_str().unwrap_or("err");
+            let description = &json["description"].as_str().unwrap_or("err");
+            return Some(Self {
+                name: name.to_string(),
+                description: description.to_string(),
+                uuid,
+                path,
+                icon: None,
+            });
+        }
+        None
+    }
+
+    pub fn from_uuid(uuid: String, bp_folder: PathBuf) -> Option<Self> {
+        let path = bp_folder.join(&uuid);
+        if let Ok(contents) = fs::read_to_string(path.join("description.json"))
+            && let Ok(json) = serde_json::from_str::<Value>(&contents)
+        {
+            let name = &json["name"].as_str().unwrap_or("err");
+            let description = &json["description"].as_str().unwrap_or("err");
+            return Some(Self {
+                name: name.to_string(),
+                description: description.to_string(),
+                uuid,
+                path,
+                icon: None,
+            });
+        }
+        None
+    }
+
+    pub fn new(
+        name: String,
+        description: String,
+        bp_folder: PathBuf,
+        icon: Option<Vec<u8>>,
+    ) -> Self {
+        let uuid = Uuid::new_v4().to_string();
+        let final_icon = if icon.is_some() {
+            icon
+        } else {
+            Some(DEFAULT_ICON.to_vec())
+        };
+        Self {
+            name,
+            description,
+            uuid: uuid.clone(),
+            path: bp_folder.join(uuid),
+            icon: final_icon,
+        }
+    }
+
+    /// `source` is the module being exported, see `layout_source`
+    pub fn export(
+        &mut self,
+        canvas: CanvasSnapshot,
+        exporter_settings: &ExporterSettings,
+        source: &str,
+    ) -> Result<(), ExportError> {
+        self.export_with_state(canvas, exporter_settings, None, source)
+            .map(|_| ())
+    }
+
+    /// exports with every part starting how it is in `state` instead of how its set to start.
+    /// blueprints cant hold whats inside a timer, so those start empty in game. returns how
+    /// many timers had ticks in them that got lost
+    pub fn export_with_state(
+        &mut self,
+        canvas: CanvasSnapshot,
+        exporter_settings: &ExporterSettings,
+        state: Option<&SaveState>,
+        source: &str,
+    ) -> Result<usize, ExportError> {
+        let top_level = top_level_positions(&canvas);
+        let RawCanvasData {
+            parts,
+            colors,
+            positions,
+            mut active,
+            connections,
+            io_parts,
+            important_parts,
+            part_paths,
+            ..
+        } = get_canvas_raw_data(canvas, true, &Vec::new());
+
+        let mut lost_timers = 0;
+        if let Some(state) = state {
+            for (i, path) in part_paths.iter().enumerate() {
+                let saved = state.parts.get(&path_key(path));
+                active[i] = saved.is_some_and(|p| p.on);
+                if saved.is_some_and(|p| !p.timer.is_empty()) {
+                    lost_timers += 1;
+                }
+            }
+        }
+
+        // treat important parts as IO
+        let io_parts: HashSet<usize> = io_parts.into_iter().chain(important_parts).collect();
+
+        let mut out_connections: Vec<Vec<usize>> = vec![Vec::new(); parts.len()];
+        for (from, to) in &connections {
+            out_connections[*from].push(*to);
+        }
+
+        let keys: Vec<String> = part_paths.iter().map(|path| path_key(path)).collect();
+        let (positioning, _) = place_parts(
+            &positions,
+            &top_level,
+            &io_parts,
+            &part_paths,
+            &out_connections,
+            exporter_settings,
+            Some((&self.path, source)),
+        );
+
+        let mut children: Vec<Value> = Vec::new();
+        for (i, part_type) in parts.iter().enumerate() {
+            let color = format!(
+                "{:02X}{:02X}{:02X}",
+                colors[i].r(),
+                colors[i].g(),
+                colors[i].b()
+            );
+            let controllers: Vec<Value> = out_connections[i]
+                .iter()
+                .map(|&id| json!({ "id": id }))
+                .collect();
+
+            let (px, py, pz) = positioning[i];
+            let is_io = io_parts.contains(&i);
+            let (xaxis, zaxis) = if is_io { (3, -2) } else { (2, 1) };
+
+            let child = match part_type {
+                PartType::Timer(buffer) => json!({
+                    "color": color,
+                    "controller": {
+                        "active": active[i],
+                        "controllers": controllers,
+                        "id": i,
+                        "joints": null,
+                        "seconds": buffer.len() / 40,
+                        "ticks": buffer.len() % 40
+                    },
+                    "pos": { "x": px, "y": py, "z": pz },
+                    "shapeId": TIMER_SHAPE_ID,
+                    "xaxis": 1,
+                    "zaxis": 3
+                }),
+                _ => {
+                    let mode = match part_type {
+                        PartType::And => 0,
+                        PartType::Or => 1,
+                        PartType::Xor => 2,
+                        PartType::Nand => 3,
+                        PartType::Nor => 4,
+                        PartType::Xnor => 5,
+                        _ => 0,
+                    };
+                    json!({
+                        "color": color,
+                        "controller": {
+                            "active": active[i],
+                            "controllers": controllers,
+                            "id": i,
+                            "joints": null,
+                            "mode": mode
+                        },
+                        "pos": { "x": px, "y": py, "z": pz },
+                        "shapeId": GATE_SHAPE_ID,
+                        "xaxis": xaxis,
+                        "zaxis": zaxis
+                    })
+                }
+            };
+            children.push(child);
+        }
+
+        // glass backing behind IO parts
+        if let Some(((x, y, z), (size_x, size_y, size_z))) = glass_backing(&positioning, &io_parts)
+        {
+            children.push(json!({
+                "bounds": { "x": size_x, "y": size_y, "z": size_z },
+                "color": GLASS_COLOR,
+                "pos": { "x": x, "y": y, "z": z },
+                "shapeId": GLASS_SHAPE_ID,
+                "xaxis": 1,
+                "zaxis": 3
+            }));
+        }
+
+        let blueprint = json!({
+            "bodies": [{ "childs": children }],
+            "version": 4
+        });
+
+        _ = fs::create_dir_all(&self.path);
+
+        if let Ok(output) = serde_json::to_string(&blueprint)
+            && fs::write(self.path.join("blueprint.json"), output).is_err()
+        {
+            return Err(ExportError::Write("blueprint.json"));
+        }
+        let layout = ExportLayout {
+            source: source.to_string(),
+            parts: keys.into_iter().zip(positioning.iter().copied()).collect(),
+        };
+        if let Ok(output) = serde_json::to_string(&layout)
+            && fs::write(self.path.join(LAYOUT_FILE), output).is_err()
+        {
+            return Err(ExportError::Write(LAYOUT_FILE));
+        }
+
+        if let Ok(output) = serde_json::to_string(
+            &json!({ "description": self.description, "localId": self.uuid, "name": self.name, "type": "Blueprint", "version": 0}),
+        ) && fs::write(self.path.join("description.json"), output).is_err()
+        {
+            if matches!(
+                fs::exists(self.path.join("description.json")),
+                Ok(true) | Err(_)
+            ) {
+                _ = fs::remove_dir_all(&self.path);
+            }
+            return Err(ExportError::Write("description.json"));
+        }
+        if let Some(icon) = self.icon.clone()
+            && fs::write(self.path.join("icon.png"), icon).is_err()
+        {
+            return Err(ExportError::Write("icon.png"));
+        }
+        Ok(lost_timers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layouts_only_load_for_the_same_module() {
+        let folder = std::env::temp_dir().join(format!("smlogic_layout_{}", std::process::id()));
+        fs::create_dir_all(&folder).unwrap();
+        let layout = ExportLayout {
+            source: "/projects/a.sml".to_string(),
+            parts: BTreeMap::from([("1".to_string(), (0, 0, 0))]),
+        };
+        fs::write(
+            folder.join(LAYOUT_FILE),
+            serde_json::to_string(&layout).unwrap(),
+        )
+        .unwrap();
+        assert!(ExportLayout::load(&folder, "/projects/a.sml").is_some());
+        assert!(ExportLayout::load(&folder, "/projects/b.sml").is_none());
+        assert!(ExportLayout::load(&folder, "").is_none());
+        _ = fs::remove_dir_all(&folder);
+    }
+
+    #[test]
+    fn layouts_put_old_parts_back() {
+        let layout = ExportLayout {
+            source: "/projects/a.sml".to_string(),
+            parts: BTreeMap::from([("1".to_string(), (5, 5, 5)), ("2".to_string(), (0, 0, 0))]),
+        };
+        let keys = ["1".to_string(), "2".to_string(), "3".to_string()];
+
+        let none = HashSet::new();
+
+        // 3 is new and its spot was taken by 1, so it goes next to it, inside the grid
+        let mut positioning = vec![(0, 0, 0), (1, 1, 1), (5, 5, 5)];
+        assert_eq!(layout.apply(&mut positioning, &keys, &none, &none), 2);
+        assert_eq!(positioning, vec![(5, 5, 5), (0, 0, 0), (4, 5, 5)]);
+
+        // skipped parts keep the spot they were given
+        let mut positioning = vec![(0, 0, 0), (1, 1, 1), (2, 2, 2)];
+        let skip = HashSet::from([1]);
+        assert_eq!(layout.apply(&mut positioning, &keys, &skip, &skip), 1);
+        assert_eq!(positioning, vec![(5, 5, 5), (1, 1, 1), (2, 2, 2)]);
+    }
+
+    #[test]
+    fn new_parts_stay_on_the_grid_and_off_io() {
+        // a full 2x2x1 grid of gates, an io part on the io plane and a layout that puts two of
+        // the gates back on the other two's spots
+        let layout = ExportLayout {
+            source: String::new(),
+            parts: BTreeMap::from([
+                ("0".to_string(), (0, 1, 0)),
+                ("1".to_string(), (1, 1, 0)),
+                ("4".to_string(), (-1, 5, 5)),
+            ]),
+        };
+        let keys: Vec<String> = (0..5).map(|i| i.to_string()).collect();
+        let mut positioning = vec![(0, 0, 0), (1, 0, 0), (0, 1, 0), (1, 1, 0), (-1, 1, 0)];
+        let io = HashSet::from([4]);
+        assert_eq!(layout.apply(&mut positioning, &keys, &io, &io), 2);
+        assert_eq!(positioning[4], (-1, 1, 0)); // skipped, stays where the canvas put it
+        let unique: HashSet<BlockPos> = positioning.iter().copied().collect();
+        assert_eq!(unique.len(), positioning.len());
+        // the two new gates fill the two spots the kept ones left
+        assert!(
+            positioning[..4]
+                .iter()
+                .all(|p| p.0 >= 0 && p.0 <= 1 && p.2 == 0)
+        );
+        assert!(positioning[..4].iter().all(|p| p.1 >= 0 && p.1 <= 1));
+    }
+
+    #[test]
+    fn canvas_placement_keeps_positions() {
+        let folder = std::env::temp_dir().join(format!("smlogic_canvas_{}", std::process::id()));
+        fs::create_dir_all(&folder).unwrap();
+        let layout = ExportLayout {
+            source: "/projects/a.sml".to_string(),
+            parts: BTreeMap::from([("2".to_string(), (4, 4, 4))]),
+        };
+        fs::write(
+            folder.join(LAYOUT_FILE),
+            serde_json::to_string(&layout).unwrap(),
+        )
+        .unwrap();
+        let settings = ExporterSettings {
+            placement: Placement::Canvas,
+            keep_positions: true,
+            ..ExporterSettings::NEW
+        };
+        let part_paths = vec![vec![1], vec![2]];
+        let positions = [Pos2::ZERO, Pos2::new(-100.0, 0.0)];
+        let top_level = HashMap::from([(1, positions[0]), (2, positions[1])]);
+        let (out, kept) = place_parts(
+            &positions,
+            &top_level,
+            &HashSet::from([0]),
+            &part_paths,
+            &[Vec::new(), Vec::new()],
+            &settings,
+            Some((&folder, "/projects/a.sml")),
+        );
+        assert_eq!(kept, 1);
+        assert_eq!(out[1], (4, 4, 4));
+        assert_eq!(out[0].0, -1);
+        _ = fs::remove_dir_all(&folder);
+    }
+
+    #[test]
+    fn canvas_layout_keeps_canvas_positions() {
+        let settings = ExporterSettings {
+            io_x_scale: 0.1,
+            io_y_scale: 0.1,
+            ..ExporterSettings::NEW
+        };
+        // an input, two gates on the same spot and a part inside a module
+        let part_paths = vec![vec![1], vec![2], vec![3], vec![4, 7]];
+        let canvas_positions = [
+            Pos2::new(0.0, 0.0),
+            Pos2::new(-10.0, 0.0),
+            Pos2::new(-10.0, 0.0),
+            Pos2::new(-20.0, -20.0),
+        ];
+        let out = canvas_layout(
+            &HashSet::from([0]),
+            &part_paths,
+            &canvas_positions,
+            &settings,
+        );
+        assert_eq!(out[0], (-1, 1, 0)); // io plane
+        assert_eq!(out[1], (0, 2, 0));
+        assert_eq!(out[3], (0, 3, 2));
+        // the second gate goes right next to the first
+        let (x, y, z) = out[2];
+        assert_eq!(x * x + (y - 2) * (y - 2) + z * z, 1);
+        assert!(x >= 0);
+        let unique: HashSet<BlockPos> = out.iter().copied().collect();
+        assert_eq!(unique.len(), out.len());
+    }
+
+    #[test]
+    fn crowded_canvas_layouts_still_get_a_block_each() {
+        let settings = ExporterSettings::NEW;
+        let part_paths: Vec<Vec<u64>> = (0..200).map(|i| vec![0, i]).collect();
+        let canvas_positions = vec![Pos2::ZERO; 200];
+        let io_parts: HashSet<usize> = (0..10).collect();
+        let out = canvas_layout(&io_parts, &part_paths, &canvas_positions, &settings);
+        let unique: HashSet<BlockPos> = out.iter().copied().collect();
+        assert_eq!(unique.len(), out.len());
+        // io parts stay on the io plane, everything else stays behind it
+        assert!(io_parts.iter().all(|&i| out[i].0 == -1));
+        assert!((10..200).all(|i| out[i].0 >= 0));
+    }
+}
@@ -0,0 +1,477 @@
+use crate::canvas::CanvasSnapshot;
+use crate::compiled::Engine;
+use crate::connections::Connection;
+use crate::error::VectorError;
+use crate::parts::{Part, PartData, PartType, Port, Timer, sort_by_position};
+use crate::simulator::{ImportantGate, SimState, effective_threads};
+use emath::Pos2;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+/// one line of an input vector file, sets an input at a certain tick
+#[derive(Clone, Debug, PartialEq)]
+pub struct InputVector {
+    pub tick: u64,
+    pub label: String,
+    pub value: bool,
+}
+
+/// parses an input vector file. every line looks like `tick: label = value`, blank lines and
+/// anything after a `#` are ignored. values can be 1/0, true/false or on/off.
+pub fn parse_vectors(contents: &str) -> Result<Vec<InputVector>, VectorError> {
+    let mut vectors = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let parse_error = |message: &str| VectorError::Parse {
+            line: i + 1,
+            message: message.to_string(),
+        };
+        let (tick, rest) = line
+            .split_once(':')
+            .ok_or_else(|| parse_error("expected `tick: label = value`"))?;
+        let tick = tick
+            .trim()
+            .parse()
+            .map_err(|_| parse_error("invalid tick"))?;
+        // split on the last = so labels can have anything in them
+        let (label, value) = rest
+            .rsplit_once('=')
+            .ok_or_else(|| parse_error("expected `label = value`"))?;
+        let value = parse_bool(value.trim()).ok_or_else(|| parse_error("invalid value"))?;
+        vectors.push(InputVector {
+            tick,
+            label: label.trim().to_string(),
+            value,
+        });
+    }
+    vectors.sort_by_key(|v| v.tick); // stable, so lines for the same tick keep their order
+    Ok(vectors)
+}
+
+pub fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "1" | "true" | "on" => Some(true),
+        "0" | "false" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// a simulation with no thread or gui attached, ticked by hand. inputs and outputs are the
+/// top level IO parts, sorted by their position on the canvas.
+pub struct HeadlessSim {
+    pub state: SimState,
+    pub inputs: Vec<(String, usize)>, // (label, simulation index)
+    pub outputs: Vec<(String, usize)>,
+    pub important_gates: Vec<ImportantGate>,
+}
+
+impl HeadlessSim {
+    pub fn new(canvas: &CanvasSnapshot) -> Self {
+        let mut canvas = canvas.clone();
+        let mut important_gates = Vec::new();
+        let state = SimState::from_canvas_snapshot(&mut canvas, &mut important_gates);
+
+        let mut io_parts: Vec<_> = canvas
+            .parts
+            .values()
+            .filter(|p| matches!(p.part_data, PartData::IO(_)))
+            .collect();
+        sort_by_position(&mut io_parts, |p| p.pos);
+
+        let mut inputs = Vec::new();
+        let mut outputs = Vec::new();
+        for part in io_parts {
+            if let (PartData::IO(io), Some(i)) = (&part.part_data, part.simulation_index) {
+                if io.input {
+                    inputs.push((part.label.clone(), i));
+                } else {
+                    outputs.push((part.label.clone(), i));
+                }
+            }
+        }
+        Self {
+            state,
+            inputs,
+            outputs,
+            important_gates,
+        }
+    }
+
+    /// records every input, output and important gate into the waveform from now on
+    pub fn record_all(&mut self) {
+        let mut signals: Vec<(String, String, usize)> = self
+            .inputs
+            .iter()
+            .chain(&self.outputs)
+            .map(|(label, i)| (label.clone(), String::new(), *i))
+            .collect();
+        signals.extend(
+            self.important_gates
+                .iter()
+                .map(|g| (g.label.clone(), g.tree.clone(), g.simulation_index)),
+        );
+        let tick = self.state.tick;
+        for (label, tree, i) in signals {
+            let value = self.state.part_outputs[i];
+            self.state.waveform.add_trace(label, tree, i, tick, value);
+        }
+    }
+
+    /// sets every input with this label, errors if there isnt one
+    pub fn set_input(&mut self, label: &str, value: bool) -> Result<(), VectorError> {
+        let mut found = false;
+        for (input_label, i) in &self.inputs {
+            if input_label == label {
+                self.state.set_output(*i, value);
+                found = true;
+            }
+        }
+        if found {
+            Ok(())
+        } else {
+            Err(VectorError::UnknownInput(label.to_string()))
+        }
+    }
+
+    /// values of every output with this label, errors if there isnt one
+    pub fn output(&self, label: &str) -> Result<Vec<bool>, VectorError> {
+        let values: Vec<bool> = self
+            .outputs
+            .iter()
+            .filter(|(output_label, _)| output_label == label)
+            .map(|(_, i)| self.state.part_outputs[*i])
+            .collect();
+        if values.is_empty() {
+            Err(VectorError::UnknownOutput(label.to_string()))
+        } else {
+            Ok(values)
+        }
+    }
+
+    pub fn output_values(&self) -> Vec<bool> {
+        self.outputs
+            .iter()
+            .map(|(_, i)| self.state.part_outputs[*i])
+            .collect()
+    }
+
+    /// runs until tick `ticks`, applying each vector once its tick is reached. returns the
+    /// output values at every tick along the way. vectors are applied before their ticks row
+    /// is recorded, so they only reach the outputs on later ticks.
+    pub fn run(
+        &mut self,
+        vectors: &[InputVector],
+        ticks: u64,
+    ) -> Result<Vec<(u64, Vec<bool>)>, VectorError> {
+        let mut vectors = vectors.iter().peekable();
+        let mut rows = Vec::new();
+        loop {
+            while let Some(vector) = vectors.next_if(|v| v.tick <= self.state.tick) {
+                self.set_input(&vector.label, vector.value)?;
+            }
+            rows.push((self.state.tick, self.output_values()));
+            if self.state.tick >= ticks {
+                break;
+            }
+            self.state.tick();
+            self.state.record_waveform();
+        }
+        Ok(rows)
+    }
+}
+
+pub struct BenchResult {
+    pub engine: Engine,
+    pub threads: usize,
+    pub time: Duration,
+    pub ticks_per_second: f64,
+    pub first_mismatch: Option<u64>, // first tick where it didnt match the simple engine
+}
+
+/// runs the vectors on one engine, calling `on_tick` after every tick. with `history` above 0
+/// it saves rewind history before every tick like the gui does
+fn run_engine(
+    canvas: &CanvasSnapshot,
+    engine: Engine,
+    threads: usize,
+    history: usize,
+    vectors: &[InputVector],
+    ticks: u64,
+    mut on_tick: impl FnMut(&SimState),
+) -> Result<Duration, VectorError> {
+    let mut sim = HeadlessSim::new(canvas);
+    sim.state.engine = engine;
+    sim.state.threads = threads;
+    sim.state.history.set_capacity(history);
+    let mut vectors = vectors.iter().peekable();
+    let start = Instant::now();
+    while sim.state.tick < ticks {
+        while let Some(vector) = vectors.next_if(|v| v.tick <= sim.state.tick) {
+            sim.set_input(&vector.label, vector.value)?;
+        }
+        sim.state.save_history();
+        sim.state.tick();
+        on_tick(&sim.state);
+    }
+    Ok(start.elapsed())
+}
+
+/// times every engine on the same run, then runs them again comparing every parts output on
+/// every tick against the simple engine. with more than 1 thread the simple engine is timed
+/// again with 2, 4, 8... up to `threads` threads, each capped to what it would really use (see
+/// `SimState::effective_threads`), so small circuits dont get a multithreaded run at all.
+/// `history` is how many ticks of rewind history to keep while timing, 0 for none
+pub fn benchmark(
+    canvas: &CanvasSnapshot,
+    vectors: &[InputVector],
+    ticks: u64,
+    threads: usize,
+    history: usize,
+) -> Result<Vec<BenchResult>, VectorError> {
+    let hash_outputs = |state: &SimState| {
+        let mut hasher = DefaultHasher::new();
+        state.part_outputs.hash(&mut hasher);
+        hasher.finish()
+    };
+    let mut expected = Vec::new();
+    let mut parts = 0;
+    run_engine(canvas, Engine::Simple, 1, 0, vectors, ticks, |state| {
+        parts = state.part_types.len();
+        expected.push(hash_outputs(state))
+    })?;
+
+    let mut results = Vec::new();
+    let mut runs: Vec<(Engine, usize)> = Engine::ENGINES.iter().map(|e| (*e, 1)).collect();
+    let counts = std::iter::successors(Some(2), |n| Some(n * 2)).take_while(|&n| n < threads);
+    for count in counts.chain([threads]) {
+        let count = effective_threads(count, parts);
+        if count > 1 && !runs.contains(&(Engine::Simple, count)) {
+            runs.push((Engine::Simple, count));
+        }
+    }
+    for (engine, threads) in runs {
+        let time = run_engine(canvas, engine, threads, history, vectors, ticks, |_| {})?;
+        let mut first_mismatch = None;
+        let mut i = 0;
+        run_engine(canvas, engine, threads, 0, vectors, ticks, |state| {
+            if first_mismatch.is_none() && hash_outputs(state) != expected[i] {
+                first_mismatch = Some(state.tick);
+            }
+            i += 1;
+        })?;
+        results.push(BenchResult {
+            engine,
+            threads,
+            time,
+            ticks_per_second: ticks as f64 / time.as_secs_f64().max(f64::EPSILON),
+            first_mismatch,
+        });
+    }
+    Ok(results)
+}
+
+/// input vectors that start a `generated_display` playing
+pub const DISPLAY_VECTORS: &str = "1: start = 1\n3: start = 0\n";
+
+/// builds the same kind of display as sample_projects/bad_apple/main.lua, `width` by `height`
+/// pixels with 8 frame buffers and 600 frames, but with noise instead of the video so it doesnt
+/// need the .bin. this is what `smlogic bench --display` runs, see `DISPLAY_VECTORS`
+pub fn generated_display(width: usize, height: usize) -> CanvasSnapshot {
+    const BUFFERS: usize = 8;
+    const FRAME_TICKS: usize = 2; // ticks per frame, 20 fps
+    const FRAMES: usize = 600;
+    let mut canvas = CanvasSnapshot::new();
+    let add = |canvas: &mut CanvasSnapshot, part: PartType| {
+        Part::add(part, canvas, Pos2::ZERO, None, &mut Vec::new())
+    };
+    let connect = |canvas: &mut CanvasSnapshot, from: u64, to: u64| {
+        canvas.connections.push(Connection {
+            start: Port {
+                part: from,
+                input: false,
+                port_id: None,
+            },
+            end: Port {
+                part: to,
+                input: true,
+                port_id: None,
+            },
+            simulation_index: None,
+        })
+    };
+    let timer = |canvas: &mut CanvasSnapshot| {
+        let id = add(canvas, PartType::Timer);
+        if let Some(part) = canvas.parts.get_mut(&id) {
+            part.part_data = PartData::Timer(Timer {
+                secs: 0,
+                ticks: (BUFFERS * FRAME_TICKS - 1) as u8,
+                active: false,
+            });
+        }
+        id
+    };
+
+    // start input, turns into a pulse as long as all the buffers
+    let input = add(&mut canvas, PartType::Input);
+    if let Some(part) = canvas.parts.get_mut(&input) {
+        part.label = "start".to_string();
+    }
+    let nand = add(&mut canvas, PartType::Nand);
+    let and = add(&mut canvas, PartType::And);
+    let xor = add(&mut canvas, PartType::Xor);
+    let pulse = timer(&mut canvas);
+    for (from, to) in [
+        (input, nand),
+        (input, and),
+        (nand, and),
+        (xor, xor),
+        (and, xor),
+    ] {
+        connect(&mut canvas, from, to);
+    }
+    connect(&mut canvas, and, pulse);
+    connect(&mut canvas, pulse, xor);
+
+    // ring of gates that turns each buffer on in turn
+    let clock: Vec<u64> = (0..BUFFERS * FRAME_TICKS)
+        .map(|_| add(&mut canvas, PartType::Or))
+        .collect();
+    for pair in clock.windows(2) {
+        connect(&mut canvas, pair[1], pair[0]);
+    }
+    connect(&mut canvas, clock[0], clock[clock.len() - 1]);
+    connect(&mut canvas, pulse, clock[clock.len() - 1]);
+    let activators: Vec<u64> = clock
+        .chunks(FRAME_TICKS)
+        .map(|ticks| {
+            let id = add(&mut canvas, PartType::Or);
+            for &tick in ticks {
+                connect(&mut canvas, tick, id);
+            }
+            id
+        })
+        .collect();
+
+    let pixels = width * height;
+    let screen: Vec<u64> = (0..pixels)
+        .map(|_| add(&mut canvas, PartType::Or))
+        .collect();
+    let mut buffers = Vec::new();
+    for i in 0..BUFFERS {
+        let mut buffer = Vec::new();
+        for &pixel in &screen {
+            let gate = add(&mut canvas, PartType::And);
+            let stored = add(&mut canvas, PartType::Or);
+            connect(&mut canvas, gate, pixel);
+            connect(&mut canvas, activators[BUFFERS - 1 - i], gate);
+            connect(&mut canvas, stored, gate);
+            buffer.push(stored);
+        }
+        buffers.push(buffer);
+    }
+
+    // every frame timer fills all the buffers, xorshift so its the same every time
+    let mut noise: u32 = 0x2545_f491;
+    let mut last_timer = xor;
+    for _ in (0..FRAMES).step_by(BUFFERS) {
+        let id = timer(&mut canvas);
+        connect(&mut canvas, last_timer, id);
+        last_timer = id;
+        for buffer in &buffers {
+            for &stored in buffer {
+                noise ^= noise << 13;
+                noise ^= noise >> 17;
+                noise ^= noise << 5;
+                if noise & 1 == 1 {
+                    connect(&mut canvas, id, stored);
+                }
+            }
+        }
+    }
+    canvas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vector(tick: u64, label: &str, value: bool) -> InputVector {
+        InputVector {
+            tick,
+            label: label.to_string(),
+            value,
+        }
+    }
+
+    #[test]
+    fn parses_vectors() {
+        let contents = "# reset first\n\
+            5: clock = on\n\
+            \n\
+            0: reset = 1 # held for a tick\n\
+            1: reset = false\n\
+            5: a = b = TRUE\n";
+        let vectors = parse_vectors(contents).unwrap();
+        assert_eq!(
+            vectors,
+            vec![
+                vector(0, "reset", true),
+                vector(1, "reset", false),
+                vector(5, "clock", true),
+                vector(5, "a = b", true),
+            ]
+        );
+    }
+
+    #[test]
+    fn vector_errors_have_line_numbers() {
+        for (contents, bad_line) in [
+            ("0: a = 1\nreset = 1", 2),
+            ("0: a = 1\n\nx: a = 1", 3),
+            ("0: a", 1),
+            ("# comment\n0: a = maybe", 2),
+        ] {
+            match parse_vectors(contents) {
+                Err(VectorError::Parse { line, .. }) => assert_eq!(line, bad_line, "{}", contents),
+                _ => panic!("{:?} should not parse", contents),
+            }
+        }
+    }
+
+    #[test]
+    fn parses_bools() {
+        for value in ["1", "true", "On", "TRUE"] {
+            assert_eq!(parse_bool(value), Some(true));
+        }
+        for value in ["0", "false", "OFF"] {
+            assert_eq!(parse_bool(value), Some(false));
+        }
+        assert_eq!(parse_bool("yes"), None);
+        assert_eq!(parse_bool(""), None);
+    }
+
+    #[test]
+    fn generated_display_plays() {
+        let canvas = generated_display(8, 6);
+        // the start pulse and clock, 17 gates a pixel and a timer every 8 frames
+        assert_eq!(canvas.parts.len(), 29 + 8 * 6 * 17 + 75);
+        let vectors = parse_vectors(DISPLAY_VECTORS).unwrap();
+        let mut sim = HeadlessSim::new(&canvas);
+        let mut lit = 0;
+        for _ in 0..100 {
+            if let Some(vector) = vectors.iter().find(|v| v.tick == sim.state.tick) {
+                sim.set_input(&vector.label, vector.value).unwrap();
+            }
+            sim.state.tick();
+            lit = lit.max(sim.state.part_outputs.iter().filter(|&&on| on).count());
+        }
+        assert!(lit > 8 * 6, "the display never started");
+        for result in benchmark(&canvas, &vectors, 100, 2, 0).unwrap() {
+            assert_eq!(result.first_mismatch, None);
+        }
+    }
+}
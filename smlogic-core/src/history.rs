@@ -0,0 +1,265 @@
+use crate::simulator::{PartType, SimState};
+use std::collections::VecDeque;
+
+pub const DEFAULT_REWIND: usize = 200; // ticks that can be stepped back once its turned on
+
+/// everything needed to put the simulation back to how it was at a tick
+#[derive(Clone)]
+pub struct SavedTick {
+    pub tick: u64,
+    pub part_outputs: Vec<bool>,
+    pub prev_outputs: Vec<bool>,
+    pub timers: Vec<VecDeque<bool>>, // buffers of every timer, in simulation index order
+}
+
+/// only what changed between two saved ticks, turns the newer one back into the older one
+struct TickDelta {
+    tick: u64, // the tick it goes back to
+    part_outputs: Vec<(usize, bool)>,
+    prev_outputs: Vec<(usize, bool)>,
+    timers: Vec<(usize, VecDeque<bool>)>, // (index into `SavedTick::timers`, old buffer)
+}
+
+/// the last few ticks. only the newest one is kept in full, everything before it is just the
+/// parts that changed, so saving a tick doesnt copy the whole simulation. a capacity of 0
+/// turns it off, which is the default since it still costs a lot every tick (the event
+/// driven engine runs main_cpu about 8x slower with it on).
+pub struct History {
+    pub capacity: usize,
+    latest: Option<SavedTick>,
+    deltas: VecDeque<TickDelta>, // oldest first, the last one goes back from `latest`
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            latest: None,
+            deltas: VecDeque::new(),
+        }
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        if capacity == 0 {
+            self.clear();
+        }
+        self.trim();
+    }
+
+    pub fn clear(&mut self) {
+        self.latest = None;
+        self.deltas.clear();
+    }
+
+    /// how many ticks can be stepped back
+    pub fn len(&self) -> usize {
+        self.latest.as_ref().map_or(0, |_| self.deltas.len() + 1)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.latest.is_none()
+    }
+
+    pub fn oldest_tick(&self) -> Option<u64> {
+        match self.deltas.front() {
+            Some(delta) => Some(delta.tick),
+            None => self.latest.as_ref().map(|t| t.tick),
+        }
+    }
+
+    fn trim(&mut self) {
+        while self.len() > self.capacity.max(1) {
+            self.deltas.pop_front();
+        }
+    }
+
+    /// turns `latest` into the tick before it, or nothing if its the oldest one
+    fn go_back(&mut self) {
+        let (Some(latest), Some(delta)) = (&mut self.latest, self.deltas.pop_back()) else {
+            self.latest = None;
+            return;
+        };
+        latest.tick = delta.tick;
+        for (i, value) in delta.part_outputs {
+            latest.part_outputs[i] = value;
+        }
+        for (i, value) in delta.prev_outputs {
+            latest.prev_outputs[i] = value;
+        }
+        for (i, buffer) in delta.timers {
+            latest.timers[i] = buffer;
+        }
+    }
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+/// copies `current` into `saved`, returning the old value of everything that changed
+fn take_changes(saved: &mut [bool], current: &[bool]) -> Vec<(usize, bool)> {
+    let mut changes = Vec::new();
+    for (i, (saved, current)) in saved.iter_mut().zip(current).enumerate() {
+        if saved != current {
+            changes.push((i, *saved));
+            *saved = *current;
+        }
+    }
+    changes
+}
+
+impl SimState {
+    fn timer_buffers(&self) -> impl Iterator<Item = &VecDeque<bool>> {
+        self.part_types.iter().filter_map(|p| match p {
+            PartType::Timer(buffer) => Some(buffer),
+            _ => None,
+        })
+    }
+
+    /// remembers the current state, called before every tick
+    pub fn save_history(&mut self) {
+        if self.history.capacity == 0 {
+            return;
+        }
+        let Some(latest) = &mut self.history.latest else {
+            self.history.latest = Some(SavedTick {
+                tick: self.tick,
+                part_outputs: self.part_outputs.clone(),
+                prev_outputs: self.prev_outputs.clone(),
+                timers: self.timer_buffers().cloned().collect(),
+            });
+            return;
+        };
+        let mut timers = Vec::new();
+        let buffers = self.part_types.iter().filter_map(|p| match p {
+            PartType::Timer(buffer) => Some(buffer),
+            _ => None,
+        });
+        for (i, (saved, buffer)) in latest.timers.iter_mut().zip(buffers).enumerate() {
+            if saved != buffer {
+                timers.push((i, std::mem::replace(saved, buffer.clone())));
+            }
+        }
+        let delta = TickDelta {
+            tick: latest.tick,
+            part_outputs: take_changes(&mut latest.part_outputs, &self.part_outputs),
+            prev_outputs: take_changes(&mut latest.prev_outputs, &self.prev_outputs),
+            timers,
+        };
+        // saved twice on the same tick, the new one just replaces it
+        if latest.tick < self.tick {
+            self.history.deltas.push_back(delta);
+        }
+        latest.tick = self.tick;
+        self.history.trim();
+    }
+
+    /// puts the simulation back to the newest saved tick, which is then forgotten
+    fn restore(&mut self) {
+        let Some(saved) = &self.history.latest else {
+            return;
+        };
+        self.compiled = None;
+        self.tick = saved.tick;
+        self.part_outputs.clone_from(&saved.part_outputs);
+        self.prev_outputs.clone_from(&saved.prev_outputs);
+        let buffers = self.part_types.iter_mut().filter_map(|p| match p {
+            PartType::Timer(buffer) => Some(buffer),
+            _ => None,
+        });
+        for (buffer, saved_buffer) in buffers.zip(&saved.timers) {
+            buffer.clone_from(saved_buffer);
+        }
+        self.history.go_back();
+        self.waveform.truncate(self.tick, &self.part_outputs);
+        self.breakpoint_hit = None;
+        self.breakpoint_outputs.clear();
+        if let Some(detector) = &mut self.oscillation {
+            detector.reset();
+        }
+    }
+
+    /// undoes the last tick, returns false if theres no history left
+    pub fn step_back(&mut self) -> bool {
+        if self.history.is_empty() {
+            return false;
+        }
+        self.restore();
+        true
+    }
+
+    /// goes back to an earlier tick, returns false if its not in the history anymore
+    pub fn rewind_to(&mut self, tick: u64) -> bool {
+        if self
+            .history
+            .oldest_tick()
+            .is_none_or(|oldest| tick < oldest)
+            || tick >= self.tick
+        {
+            return false;
+        }
+        // walk the saved tick back first so the simulation only gets copied into once
+        while self.history.latest.as_ref().is_some_and(|t| t.tick > tick) {
+            self.history.go_back();
+        }
+        self.restore();
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::CanvasSnapshot;
+
+    fn semantics_sim() -> SimState {
+        let project =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../sample_projects/test");
+        let mut errors = Vec::new();
+        let mut canvas = CanvasSnapshot::load(
+            project.join("semantics.sml"),
+            Some(project),
+            &mut errors,
+            Vec::new(),
+        )
+        .expect("sample project should load");
+        SimState::from_canvas_snapshot(&mut canvas, &mut Vec::new())
+    }
+
+    fn snapshot(state: &SimState) -> (u64, Vec<bool>, Vec<bool>, Vec<VecDeque<bool>>) {
+        (
+            state.tick,
+            state.part_outputs.clone(),
+            state.prev_outputs.clone(),
+            state.timer_buffers().cloned().collect(),
+        )
+    }
+
+    #[test]
+    fn stepping_back_gives_the_same_states() {
+        let mut state = semantics_sim();
+        state.history.set_capacity(10);
+        let mut states = Vec::new();
+        for tick in 0..30 {
+            if tick % 4 == 0 {
+                let i = tick % state.part_outputs.len();
+                state.set_output(i, !state.part_outputs[i]);
+            }
+            states.push(snapshot(&state));
+            state.save_history();
+            state.tick();
+        }
+        assert_eq!(state.history.len(), 10);
+        assert!(state.rewind_to(25));
+        assert!(snapshot(&state) == states[25]);
+        for tick in (20..25).rev() {
+            assert!(state.step_back());
+            assert!(snapshot(&state) == states[tick]);
+        }
+        assert!(!state.step_back());
+        assert!(!state.rewind_to(3));
+    }
+}
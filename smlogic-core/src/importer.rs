@@ -0,0 +1,330 @@
+use crate::canvas::CanvasSnapshot;
+use crate::connections::{Connection, connection_depth};
+use crate::error::ImportError;
+use crate::exporter::{BluePrint, GATE_SHAPE_ID, TIMER_SHAPE_ID};
+use crate::parts::{
+    DEFAULT_GATE_COLOR, GATE_SIZE, Gate, GateType, IO, Part, PartData, Port, Timer,
+};
+use ecolor::Color32;
+use emath::Pos2;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+
+const COLUMN_GAP: f32 = 80.0; // space between columns of the auto layout
+const ROW_GAP: f32 = 20.0;
+
+enum Kind {
+    Gate(GateType),
+    Timer { secs: u8, ticks: u8 },
+    Other, // switches, buttons, lamps, anything else with a controller
+}
+
+/// a part of the blueprint with a controller, so anything that can be wired up
+struct Controller {
+    id: u64,
+    kind: Kind,
+    color: Color32,
+    pos: (i64, i64, i64),
+    active: bool,
+    targets: Vec<u64>, // controller ids it drives
+}
+
+fn read_controller(child: &Value) -> Option<Controller> {
+    let controller = child.get("controller")?;
+    let id = controller["id"].as_u64()?;
+    let kind = match child["shapeId"].as_str() {
+        Some(GATE_SHAPE_ID) => Kind::Gate(match controller["mode"].as_u64() {
+            Some(1) => GateType::Or,
+            Some(2) => GateType::Xor,
+            Some(3) => GateType::Nand,
+            Some(4) => GateType::Nor,
+            Some(5) => GateType::Xnor,
+            _ => GateType::And,
+        }),
+        Some(TIMER_SHAPE_ID) => Kind::Timer {
+            secs: controller["seconds"].as_u64().unwrap_or(0).min(59) as u8,
+            ticks: controller["ticks"].as_u64().unwrap_or(0).min(40) as u8,
+        },
+        _ => Kind::Other,
+    };
+    let color = child["color"]
+        .as_str()
+        .and_then(|hex| Color32::from_hex(&format!("#{}", hex.trim_start_matches('#'))).ok())
+        .unwrap_or(DEFAULT_GATE_COLOR);
+    let pos = &child["pos"];
+    let targets = controller["controllers"]
+        .as_array()
+        .map(|c| c.iter().filter_map(|c| c["id"].as_u64()).collect())
+        .unwrap_or_default();
+    Some(Controller {
+        id,
+        kind,
+        color,
+        pos: (
+            pos["x"].as_i64().unwrap_or(0),
+            pos["y"].as_i64().unwrap_or(0),
+            pos["z"].as_i64().unwrap_or(0),
+        ),
+        active: controller["active"].as_bool().unwrap_or(false),
+        targets,
+    })
+}
+
+/// rebuilds a canvas out of a blueprints json. gates and timers come back as they are, parts
+/// that drive logic (switches, buttons) become inputs and parts driven by it (lamps) become
+/// outputs. outputs only take one wire, so a lamp with more going into it becomes an OR gate
+/// feeding the output, which shows up a tick later than the lamp would. everything is laid out
+/// left to right by how far it is from an input, since blueprints dont have anything like
+/// canvas positions
+pub fn canvas_from_blueprint(blueprint: &Value) -> Result<CanvasSnapshot, ImportError> {
+    let mut controllers: Vec<Controller> = blueprint["bodies"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|body| body["childs"].as_array().into_iter().flatten())
+        .filter_map(read_controller)
+        .collect();
+    // top to bottom like in game, so labels and the layout dont change between imports
+    controllers.sort_by_key(|c| (-c.pos.2, c.pos.1, c.pos.0, c.id));
+    let count = controllers.len();
+    let index: HashMap<u64, usize> = controllers
+        .iter()
+        .enumerate()
+        .map(|(i, c)| (c.id, i))
+        .collect();
+    let targets: Vec<Vec<usize>> = controllers
+        .iter()
+        .map(|c| {
+            c.targets
+                .iter()
+                .filter_map(|id| index.get(id).copied())
+                .collect()
+        })
+        .collect();
+    let mut driven_by = vec![0; count];
+    for &target in targets.iter().flatten() {
+        driven_by[target] += 1;
+    }
+    let is_logic = |i: usize| !matches!(controllers[i].kind, Kind::Other);
+
+    let mut inputs = 0;
+    let mut outputs = 0;
+    let mut parts: Vec<Option<(PartData, String)>> = Vec::with_capacity(count);
+    let mut merged: Vec<(usize, String)> = Vec::new(); // (or gate, output label) for lamps
+
+    for (i, controller) in controllers.iter().enumerate() {
+        parts.push(match controller.kind {
+            Kind::Gate(ref gate_type) => Some((
+                PartData::Gate(Gate {
+                    gate_type: gate_type.clone(),
+                    important: false,
+                    active: controller.active,
+                }),
+                gate_type.to_label(),
+            )),
+            Kind::Timer { secs, ticks } => Some((
+                PartData::Timer(Timer {
+                    secs,
+                    ticks,
+                    active: controller.active,
+                }),
+                "Timer".to_string(),
+            )),
+            Kind::Other if targets[i].iter().any(|&t| is_logic(t)) => {
+                inputs += 1;
+                Some((
+                    PartData::IO(IO { input: true }),
+                    format!("Input {}", inputs),
+                ))
+            }
+            Kind::Other if driven_by[i] > 0 => {
+                outputs += 1;
+                let label = format!("Output {}", outputs);
+                if driven_by[i] > 1 {
+                    // lamps turn on if anything going into them is
+                    merged.push((i, label));
+                    let gate = Gate {
+                        gate_type: GateType::Or,
+                        important: false,
+                        active: false,
+                    };
+                    Some((PartData::Gate(gate), GateType::Or.to_label()))
+                } else {
+                    Some((PartData::IO(IO { input: false }), label))
+                }
+            }
+            Kind::Other => None,
+        });
+    }
+    let mut colors: Vec<Color32> = controllers.iter().map(|c| c.color).collect();
+    let mut fed_by: Vec<(usize, usize)> = Vec::new(); // (output, the or gate going into it)
+    for (gate, label) in merged {
+        fed_by.push((parts.len(), gate));
+        parts.push(Some((PartData::IO(IO { input: false }), label)));
+        colors.push(colors[gate]);
+    }
+    let count = parts.len();
+    let takes_input = |i: usize| match &parts[i] {
+        Some((PartData::IO(io), _)) => !io.input,
+        Some(_) => true,
+        None => false,
+    };
+    let mut wires: Vec<Vec<usize>> = (0..count)
+        .map(|i| match (&parts[i], targets.get(i)) {
+            (Some(_), Some(targets)) => targets
+                .iter()
+                .copied()
+                .filter(|&t| takes_input(t))
+                .collect(),
+            _ => Vec::new(),
+        })
+        .collect();
+    for (output, gate) in fed_by {
+        wires[gate].push(output);
+    }
+    let order: Vec<usize> = (0..count).filter(|&i| parts[i].is_some()).collect();
+    if order.is_empty() {
+        return Err(ImportError::NoLogic);
+    }
+
+    // columns are how many wires away from something with no inputs each part is. loops that
+    // nothing drives start wherever they are first in game
+    let depth = connection_depth(&wires, &order);
+    // outputs all go in their own column at the end
+    let last_column = order.iter().map(|&i| depth[i]).max().unwrap_or(0) + 1;
+    let mut columns: Vec<Vec<usize>> = vec![Vec::new(); last_column + 1];
+    for &i in &order {
+        let column = match &parts[i] {
+            Some((PartData::IO(io), _)) if !io.input => last_column,
+            _ => depth[i],
+        };
+        columns[column].push(i);
+    }
+
+    let mut canvas = CanvasSnapshot::new();
+    let mut part_ids: Vec<Option<u64>> = vec![None; count];
+    for (x, column) in columns.iter().enumerate() {
+        for (y, &i) in column.iter().enumerate() {
+            let Some((part_data, label)) = parts[i].take() else {
+                continue;
+            };
+            let id = canvas.next_id;
+            canvas.next_id += 1;
+            canvas.parts.insert(
+                id,
+                Part {
+                    id,
+                    part_data,
+                    pos: Pos2::new(
+                        x as f32 * (GATE_SIZE.x + COLUMN_GAP),
+                        y as f32 * (GATE_SIZE.y + ROW_GAP),
+                    ),
+                    label,
+                    color: colors[i],
+                    simulation_index: None,
+                },
+            );
+            part_ids[i] = Some(id);
+        }
+    }
+    for (i, wires) in wires.iter().enumerate() {
+        for &target in wires {
+            if let (Some(start), Some(end)) = (part_ids[i], part_ids[target]) {
+                canvas.connections.push(Connection {
+                    start: Port {
+                        part: start,
+                        input: false,
+                        port_id: None,
+                    },
+                    end: Port {
+                        part: end,
+                        input: true,
+                        port_id: None,
+                    },
+                    simulation_index: None,
+                });
+            }
+        }
+    }
+    Ok(canvas)
+}
+
+impl BluePrint {
+    /// reads the blueprint back into a canvas, see `canvas_from_blueprint`
+    pub fn import(&self) -> Result<CanvasSnapshot, ImportError> {
+        let contents =
+            fs::read_to_string(self.path.join("blueprint.json")).map_err(ImportError::Read)?;
+        let json: Value = serde_json::from_str(&contents).map_err(ImportError::Json)?;
+        canvas_from_blueprint(&json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn long_timers_keep_their_delay() {
+        let blueprint = json!({ "bodies": [{ "childs": [{
+            "shapeId": TIMER_SHAPE_ID,
+            "color": "DF7F01",
+            "pos": { "x": 0, "y": 0, "z": 0 },
+            "controller": { "id": 1, "seconds": 7, "ticks": 20, "controllers": [] }
+        }] }] });
+        let canvas = canvas_from_blueprint(&blueprint).unwrap();
+        let part = canvas.parts.values().next().unwrap();
+        let PartData::Timer(timer) = &part.part_data else {
+            panic!("timer imported as something else");
+        };
+        assert_eq!((timer.secs, timer.ticks), (7, 20));
+        assert_eq!(timer.clone().get_ticks(), 300);
+        let raw = crate::simulator::get_canvas_raw_data(canvas, true, &Vec::new());
+        assert!(matches!(&raw.parts[0], crate::simulator::PartType::Timer(b) if b.len() == 300));
+    }
+
+    #[test]
+    fn lamps_with_many_wires_get_an_or_gate() {
+        let part = |id: u64, shape: &str, targets: &[u64]| {
+            json!({
+                "shapeId": shape,
+                "color": "DF7F01",
+                "pos": { "x": 0, "y": id, "z": 0 },
+                "controller": {
+                    "id": id,
+                    "mode": 1,
+                    "controllers": targets.iter().map(|t| json!({ "id": t })).collect::<Vec<_>>()
+                }
+            })
+        };
+        // two switches, each through an OR gate into the same lamp
+        let blueprint = json!({ "bodies": [{ "childs": [
+            part(1, "switch", &[3]),
+            part(2, "switch", &[4]),
+            part(3, GATE_SHAPE_ID, &[5]),
+            part(4, GATE_SHAPE_ID, &[5]),
+            part(5, "lamp", &[]),
+        ] }] });
+        let canvas = canvas_from_blueprint(&blueprint).unwrap();
+        let gates = canvas
+            .parts
+            .values()
+            .filter(|p| matches!(p.part_data, PartData::Gate(_)))
+            .count();
+        assert_eq!(gates, 3);
+        let mut sim = crate::headless::HeadlessSim::new(&canvas);
+        assert_eq!(sim.inputs.len(), 2);
+        assert_eq!(sim.outputs.len(), 1);
+        assert_eq!(sim.outputs[0].0, "Output 1");
+        assert!(sim.important_gates.is_empty());
+        for (a, b) in [(false, false), (true, false), (false, true), (true, true)] {
+            sim.set_input("Input 1", a).unwrap();
+            sim.set_input("Input 2", b).unwrap();
+            for _ in 0..3 {
+                sim.state.tick();
+            }
+            assert_eq!(sim.output("Output 1").unwrap(), vec![a || b]);
+        }
+    }
+}
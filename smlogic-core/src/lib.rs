@@ -0,0 +1,27 @@
+//! the core model behind smlogic: modules, loading/saving, flattening, simulation and exporting
+//! to scrap mechanic blueprints. nothing in here depends on egui, so it can be used by other
+//! tools without opening a window.
+
+pub mod breakpoints;
+pub mod bus;
+pub mod canvas;
+pub mod compiled;
+pub mod connections;
+pub mod error;
+pub mod exporter;
+pub mod headless;
+pub mod history;
+pub mod importer;
+pub mod memory;
+pub mod oscillation;
+pub mod parts;
+pub mod savestate;
+pub mod semantics;
+pub mod simulator;
+pub mod stimulus;
+pub mod testing;
+pub mod vcd;
+pub mod waveform;
+
+pub use ecolor::Color32;
+pub use emath::{Pos2, Rect, Vec2};
@@ -0,0 +1,319 @@
+use crate::breakpoints::bus_value;
+use crate::bus::MAX_BUS_WIDTH;
+use crate::canvas::CanvasSnapshot;
+use crate::parts::{Part, PartData, sort_by_position};
+use crate::simulator::{PartType, SimState};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+
+const LATCH_LOOP: usize = 4; // longest loop through a bit thats counted as the latch holding it
+
+/// a row of registers looked at as memory, every word is a list of part paths (see
+/// `RawCanvasData::part_paths`) with the most significant bit first. saved with the module
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Memory {
+    pub name: String,
+    pub words: Vec<Vec<Vec<u64>>>,
+}
+
+/// the bits of one register module, its important gates if it has any, otherwise its outputs
+fn module_bits(module_id: u64, canvas: &CanvasSnapshot) -> Vec<Vec<u64>> {
+    let mut important: Vec<&Part> = canvas
+        .parts
+        .values()
+        .filter(|p| matches!(&p.part_data, PartData::Gate(gate) if gate.important))
+        .collect();
+    if important.is_empty() {
+        important = canvas
+            .parts
+            .values()
+            .filter(|p| matches!(&p.part_data, PartData::IO(io) if !io.input))
+            .collect();
+    }
+    sort_by_position(&mut important, |p| p.pos);
+    important
+        .iter()
+        .take(MAX_BUS_WIDTH)
+        .map(|p| vec![module_id, p.id])
+        .collect()
+}
+
+impl Memory {
+    /// one word per module instance, in position order
+    pub fn from_modules(name: String, canvas: &CanvasSnapshot, ids: &[u64]) -> Self {
+        let mut modules: Vec<&Part> = ids
+            .iter()
+            .filter_map(|id| canvas.parts.get(id))
+            .filter(|p| matches!(p.part_data, PartData::Module(_)))
+            .collect();
+        sort_by_position(&mut modules, |p| p.pos);
+        let words = modules
+            .iter()
+            .filter_map(|p| match &p.part_data {
+                PartData::Module(module) => Some(module_bits(p.id, &module.canvas_snapshot)),
+                _ => None,
+            })
+            .filter(|bits| !bits.is_empty())
+            .collect();
+        Self { name, words }
+    }
+
+    /// one word per label, so gates called "r0" are the first word, "r1" the next and so on.
+    /// words are ordered by where their first bit is
+    pub fn from_labels(name: String, canvas: &CanvasSnapshot, ids: &[u64]) -> Self {
+        let mut parts: Vec<&Part> = ids
+            .iter()
+            .filter_map(|id| canvas.parts.get(id))
+            .filter(|p| {
+                matches!(
+                    p.part_data,
+                    PartData::Gate(_) | PartData::Timer(_) | PartData::IO(_)
+                )
+            })
+            .collect();
+        sort_by_position(&mut parts, |p| p.pos);
+        let mut labels: Vec<&str> = Vec::new();
+        let mut groups: HashMap<&str, Vec<Vec<u64>>> = HashMap::new();
+        for part in parts {
+            let bits = groups.entry(&part.label).or_insert_with(|| {
+                labels.push(&part.label);
+                Vec::new()
+            });
+            if bits.len() < MAX_BUS_WIDTH {
+                bits.push(vec![part.id]);
+            }
+        }
+        let words = labels
+            .iter()
+            .filter_map(|label| groups.remove(label))
+            .collect();
+        Self { name, words }
+    }
+
+    pub fn word_width(&self) -> usize {
+        self.words.iter().map(|w| w.len()).max().unwrap_or(0)
+    }
+
+    /// simulation indexes of every word, none for words with parts that arent simulated
+    pub fn indexes(&self, path_indexes: &HashMap<Vec<u64>, usize>) -> Vec<Option<Vec<usize>>> {
+        self.words
+            .iter()
+            .map(|bits| {
+                bits.iter()
+                    .map(|path| path_indexes.get(path).copied())
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+impl SimState {
+    /// writes a word of memory. forcing a bit only lasts a tick if something else is holding
+    /// the old value, so the write goes through to the gate actually holding it (see
+    /// `holding_gate`), and every gate on a short loop through that gets worked out again from
+    /// the new value until the latch agrees (the other half of a pair of NORs for example)
+    pub fn write_word(&mut self, bits: &[usize], value: u64) {
+        let holding: Vec<usize> = bits.iter().map(|&bit| self.holding_gate(bit)).collect();
+        self.set_bus(bits, value);
+        self.set_bus(&holding, value);
+        let mut latches: Vec<usize> = holding
+            .iter()
+            .flat_map(|&bit| self.latch_gates(bit))
+            .collect();
+        latches.sort_unstable();
+        latches.dedup();
+        latches.retain(|i| !holding.contains(i) && !bits.contains(i));
+        for _ in 0..LATCH_LOOP {
+            let mut changed = false;
+            for &i in &latches {
+                if let Some(value) = self.next_output(i)
+                    && value != self.part_outputs[i]
+                {
+                    self.set_output(i, value);
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+    }
+
+    /// outputs (and any other gate with one input) just copy their input, so the gate holding
+    /// a bit is the first one up the chain that doesnt
+    fn holding_gate(&self, mut bit: usize) -> usize {
+        for _ in 0..self.part_types.len() {
+            let passes_through = matches!(
+                self.part_types[bit],
+                PartType::And | PartType::Or | PartType::Xor
+            );
+            match self.part_inputs[bit][..] {
+                [input] if passes_through && input != bit => bit = input,
+                _ => break,
+            }
+        }
+        bit
+    }
+
+    /// what a gate would turn into next tick from the current outputs, none for timers
+    fn next_output(&self, i: usize) -> Option<bool> {
+        let inputs = &self.part_inputs[i];
+        let on = inputs
+            .iter()
+            .filter(|&&input| self.part_outputs[input])
+            .count();
+        Some(match self.part_types[i] {
+            PartType::And => on == inputs.len(),
+            PartType::Or => on > 0,
+            PartType::Xor => on % 2 == 1,
+            PartType::Nand => on != inputs.len(),
+            PartType::Nor => on == 0,
+            PartType::Xnor => on % 2 == 0,
+            PartType::Timer(_) => return None,
+        })
+    }
+
+    /// every gate on a feedback loop through `bit` thats at most `LATCH_LOOP` gates long
+    fn latch_gates(&self, bit: usize) -> Vec<usize> {
+        // how many gates each part is away from feeding into the bit
+        let mut to_bit = HashMap::from([(bit, 0)]);
+        let mut frontier = vec![bit];
+        for distance in 1..LATCH_LOOP {
+            let mut next = Vec::new();
+            for &i in &frontier {
+                for &input in &self.part_inputs[i] {
+                    if let Entry::Vacant(entry) = to_bit.entry(input) {
+                        entry.insert(distance);
+                        next.push(input);
+                    }
+                }
+            }
+            frontier = next;
+        }
+        // and how far the bit is from feeding into them, anything on a short loop is in both
+        let mut from_bit = HashMap::from([(bit, 0)]);
+        let mut frontier = vec![bit];
+        for distance in 1..LATCH_LOOP {
+            let next: Vec<usize> = to_bit
+                .keys()
+                .copied()
+                .filter(|i| !from_bit.contains_key(i))
+                .filter(|&i| {
+                    self.part_inputs[i]
+                        .iter()
+                        .any(|input| frontier.contains(input))
+                })
+                .collect();
+            for &i in &next {
+                from_bit.insert(i, distance);
+            }
+            frontier = next;
+        }
+        to_bit
+            .into_iter()
+            .filter(|&(i, to)| {
+                i != bit && from_bit.get(&i).is_some_and(|from| from + to <= LATCH_LOOP)
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+/// the value of every word, using indexes from `Memory::indexes`
+pub fn word_values(indexes: &[Option<Vec<usize>>], outputs: &[bool]) -> Vec<Option<u64>> {
+    indexes
+        .iter()
+        .map(|bits| {
+            let bits = bits.as_ref()?;
+            bits.iter()
+                .all(|&i| i < outputs.len())
+                .then(|| bus_value(bits, outputs))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connections::Connection;
+    use crate::parts::{PartType as Kind, Port};
+    use emath::Pos2;
+
+    fn connect(canvas: &mut CanvasSnapshot, from: u64, to: u64) {
+        canvas.connections.push(Connection {
+            start: Port {
+                part: from,
+                input: false,
+                port_id: None,
+            },
+            end: Port {
+                part: to,
+                input: true,
+                port_id: None,
+            },
+            simulation_index: None,
+        });
+    }
+
+    fn read(state: &SimState, bits: &[usize]) -> u64 {
+        bus_value(bits, &state.part_outputs)
+    }
+
+    fn run(state: &mut SimState, ticks: u64) {
+        for _ in 0..ticks {
+            state.tick();
+        }
+    }
+
+    #[test]
+    fn nor_latches_keep_written_words() {
+        let mut canvas = CanvasSnapshot::new();
+        let mut add = |kind: Kind| Part::add(kind, &mut canvas, Pos2::ZERO, None, &mut Vec::new());
+        let (set, reset) = (add(Kind::Input), add(Kind::Input));
+        let (q, not_q) = (add(Kind::Nor), add(Kind::Nor));
+        for (from, to) in [(reset, q), (not_q, q), (set, not_q), (q, not_q)] {
+            connect(&mut canvas, from, to);
+        }
+        if let Some(PartData::Gate(gate)) = canvas.parts.get_mut(&not_q).map(|p| &mut p.part_data) {
+            gate.active = true;
+        }
+        let mut state = SimState::from_canvas_snapshot(&mut canvas, &mut Vec::new());
+        let bits = [canvas.parts[&q].simulation_index.unwrap()];
+        run(&mut state, 5);
+        assert_eq!(read(&state, &bits), 0);
+
+        // forcing just the bit gets undone by the other NOR
+        state.set_bus(&bits, 1);
+        run(&mut state, 5);
+        assert_eq!(read(&state, &bits), 0);
+
+        for value in [1, 0, 1] {
+            state.write_word(&bits, value);
+            run(&mut state, 5);
+            assert_eq!(read(&state, &bits), value);
+        }
+    }
+
+    #[test]
+    fn sample_registers_keep_written_words() {
+        let module = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../sample_projects/6502/random_modules/8_ bit_register.sml");
+        let mut canvas = CanvasSnapshot::load(module, None, &mut Vec::new(), Vec::new())
+            .expect("sample register should load");
+        let mut state = SimState::from_canvas_snapshot(&mut canvas, &mut Vec::new());
+        let bits: Vec<usize> = (0..8)
+            .rev()
+            .map(|bit| {
+                let label = format!("read {}", bit);
+                let part = canvas.parts.values().find(|p| p.label == label).unwrap();
+                part.simulation_index.unwrap()
+            })
+            .collect();
+        for value in [0xa5, 0x3c, 0] {
+            state.write_word(&bits, value);
+            run(&mut state, 10);
+            assert_eq!(read(&state, &bits), value);
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use crate::simulator::{PartType, SimState};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+
+pub const DEFAULT_WINDOW: u32 = 16; // ticks in a row a part has to toggle to count as oscillating
+pub const CYCLE_HISTORY: usize = 1024; // ticks of whole circuit states kept to find repeats
+
+/// watches for parts that flip every single tick, and for the whole circuit going round in a
+/// loop (which catches slower oscillators, like a ring of 3 NOR gates). both only count while
+/// everything driven from outside (inputs, stimuli, anything else with no inputs) holds still,
+/// so a counter on a clock stimulus doesnt show up
+pub struct OscillationDetector {
+    pub window: u32,
+    sources: Option<u64>,    // hash of the parts with no inputs
+    streaks: Vec<u32>,       // how many ticks in a row each part has changed
+    seen: HashMap<u64, u64>, // state hash -> last tick it was seen
+    seen_order: VecDeque<u64>,
+    pub oscillating: Vec<usize>,   // simulation indexes
+    pub cycle: Option<(u64, u64)>, // (period, tick it started repeating at)
+}
+
+impl OscillationDetector {
+    pub fn new(window: u32) -> Self {
+        Self {
+            window,
+            sources: None,
+            streaks: Vec::new(),
+            seen: HashMap::new(),
+            seen_order: VecDeque::new(),
+            oscillating: Vec::new(),
+            cycle: None,
+        }
+    }
+
+    /// forgets everything, for when the state is changed by something other than a tick
+    pub fn reset(&mut self) {
+        self.sources = None;
+        self.streaks.clear();
+        self.seen.clear();
+        self.seen_order.clear();
+        self.oscillating.clear();
+        self.cycle = None;
+    }
+
+    fn update(&mut self, state: &SimState) {
+        let (tick, outputs, prev) = (state.tick, &state.part_outputs, &state.prev_outputs);
+        let mut hasher = DefaultHasher::new();
+        for (output, inputs) in outputs.iter().zip(&state.part_inputs) {
+            if inputs.is_empty() {
+                output.hash(&mut hasher);
+            }
+        }
+        let sources = hasher.finish();
+        if self.sources != Some(sources) {
+            self.reset();
+            self.sources = Some(sources);
+        }
+
+        self.streaks.resize(outputs.len(), 0);
+        self.oscillating.clear();
+        for (i, streak) in self.streaks.iter_mut().enumerate() {
+            if outputs[i] != prev[i] {
+                *streak = streak.saturating_add(1);
+                if *streak >= self.window {
+                    self.oscillating.push(i);
+                }
+            } else {
+                *streak = 0;
+            }
+        }
+
+        let mut hasher = DefaultHasher::new();
+        outputs.hash(&mut hasher);
+        for part_type in &state.part_types {
+            if let PartType::Timer(buffer) = part_type {
+                buffer.hash(&mut hasher);
+            }
+        }
+        let hash = hasher.finish();
+        match self.seen.insert(hash, tick) {
+            // same state as last tick is just the circuit being stable
+            Some(last) if tick - last > 1 => {
+                let period = tick - last;
+                let start = match self.cycle {
+                    Some((old_period, start)) if old_period == period => start,
+                    _ => last,
+                };
+                self.cycle = Some((period, start));
+            }
+            Some(_) => self.cycle = None,
+            None => {
+                self.cycle = None;
+                self.seen_order.push_back(hash);
+            }
+        }
+        while self.seen_order.len() > CYCLE_HISTORY {
+            if let Some(old) = self.seen_order.pop_front() {
+                self.seen.remove(&old);
+            }
+        }
+    }
+}
+
+impl Default for OscillationDetector {
+    fn default() -> Self {
+        Self::new(DEFAULT_WINDOW)
+    }
+}
+
+impl SimState {
+    /// updates the oscillation detector if its turned on, called after every tick
+    pub fn check_oscillation(&mut self) {
+        if let Some(mut detector) = self.oscillation.take() {
+            detector.update(self);
+            self.oscillation = Some(detector);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::CanvasSnapshot;
+    use crate::connections::Connection;
+    use crate::parts::{Part, PartType as Kind, Port};
+    use crate::stimulus::Stimulus;
+    use emath::Pos2;
+
+    fn add(canvas: &mut CanvasSnapshot, kind: Kind, inputs: &[u64]) -> u64 {
+        let id = Part::add(kind, canvas, Pos2::ZERO, None, &mut Vec::new());
+        for &input in inputs {
+            connect(canvas, input, id);
+        }
+        id
+    }
+
+    fn connect(canvas: &mut CanvasSnapshot, from: u64, to: u64) {
+        canvas.connections.push(Connection {
+            start: Port {
+                part: from,
+                input: false,
+                port_id: None,
+            },
+            end: Port {
+                part: to,
+                input: true,
+                port_id: None,
+            },
+            simulation_index: None,
+        });
+    }
+
+    /// an XOR that flips whenever `clock` turns on, so one bit of a ripple counter
+    fn counter_bit(canvas: &mut CanvasSnapshot, clock: u64) -> u64 {
+        let delayed = add(canvas, Kind::Nor, &[clock]);
+        let rising = add(canvas, Kind::And, &[clock, delayed]);
+        let bit = add(canvas, Kind::Xor, &[rising]);
+        connect(canvas, bit, bit);
+        bit
+    }
+
+    /// runs with the detector on, returns what it found on every tick as (oscillating, cycle)
+    fn run(state: &mut SimState, ticks: u64, mut on_tick: impl FnMut(&SimState)) -> Vec<bool> {
+        state.oscillation = Some(OscillationDetector::default());
+        let mut found = Vec::new();
+        for _ in 0..ticks {
+            state.tick();
+            state.apply_stimuli();
+            state.check_oscillation();
+            on_tick(state);
+            let detector = state.oscillation.as_ref().unwrap();
+            found.push(!detector.oscillating.is_empty() || detector.cycle.is_some());
+        }
+        found
+    }
+
+    #[test]
+    fn clocked_counters_arent_oscillating() {
+        let mut canvas = CanvasSnapshot::new();
+        let clock = add(&mut canvas, Kind::Input, &[]);
+        let low = counter_bit(&mut canvas, clock);
+        let high = counter_bit(&mut canvas, low);
+        let mut state = SimState::from_canvas_snapshot(&mut canvas, &mut Vec::new());
+        let index = |id: u64| canvas.parts[&id].simulation_index.unwrap();
+        state.set_stimulus(index(clock), Some(Stimulus::Clock { period: 2, duty: 1 }));
+        let (low, high) = (index(low), index(high));
+        let mut counts = Vec::new();
+        let found = run(&mut state, 300, |state| {
+            counts.push(state.part_outputs[low] as u8 + 2 * state.part_outputs[high] as u8)
+        });
+        assert!(found.iter().all(|&found| !found));
+        // it really counted, through every value
+        assert!((0..4).all(|count| counts.contains(&count)));
+    }
+
+    #[test]
+    fn loops_still_get_found_next_to_inputs() {
+        let mut canvas = CanvasSnapshot::new();
+        let input = add(&mut canvas, Kind::Input, &[]);
+        let not = add(&mut canvas, Kind::Nor, &[]);
+        connect(&mut canvas, not, not);
+        add(&mut canvas, Kind::And, &[input]);
+        let mut state = SimState::from_canvas_snapshot(&mut canvas, &mut Vec::new());
+        let found = run(&mut state, 40, |_| {});
+        assert!(found[30..].iter().all(|&found| found));
+        let detector = state.oscillation.as_ref().unwrap();
+        assert_eq!(
+            detector.oscillating,
+            vec![canvas.parts[&not].simulation_index.unwrap()]
+        );
+        assert!(matches!(detector.cycle, Some((2, _))));
+    }
+}
@@ -0,0 +1,203 @@
+use crate::error::StateError;
+use crate::simulator::{PartType, SimState};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+pub const STATE_EXTENSION: &str = "smstate";
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+/// the state of one part. parts that are off with an empty timer arent saved at all
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct SavedPart {
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub on: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub was_on: bool, // last ticks output, only matters for parts with no inputs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub timer: Vec<bool>,
+}
+
+/// a whole simulation saved to a file. parts are keyed by their part path (see
+/// `RawCanvasData::part_paths`) instead of simulation index, so it still loads after the
+/// module is reopened or edited
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SaveState {
+    pub tick: u64,
+    pub parts: BTreeMap<String, SavedPart>,
+}
+
+/// `[4, 12]` -> `"4/12"`
+pub fn path_key(path: &[u64]) -> String {
+    path.iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+impl SaveState {
+    pub fn capture(state: &SimState) -> Self {
+        let mut parts = BTreeMap::new();
+        for (i, path) in state.part_paths.iter().enumerate() {
+            let timer = match &state.part_types[i] {
+                PartType::Timer(buffer) if buffer.iter().any(|v| *v) => {
+                    buffer.iter().copied().collect()
+                }
+                _ => Vec::new(),
+            };
+            let part = SavedPart {
+                on: state.part_outputs[i],
+                was_on: state.prev_outputs[i],
+                timer,
+            };
+            if part != SavedPart::default() {
+                parts.insert(path_key(path), part);
+            }
+        }
+        Self {
+            tick: state.tick,
+            parts,
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), StateError> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, StateError> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+}
+
+impl SimState {
+    /// replaces the whole simulation with a saved state, anything not in it is turned off.
+    /// returns how many saved parts dont exist anymore.
+    pub fn apply_save_state(&mut self, save: &SaveState) -> Result<usize, StateError> {
+        let indexes: HashMap<String, usize> = self
+            .part_paths
+            .iter()
+            .enumerate()
+            .map(|(i, path)| (path_key(path), i))
+            .collect();
+        let missing = save
+            .parts
+            .keys()
+            .filter(|key| !indexes.contains_key(*key))
+            .count();
+        if missing > 0 && missing == save.parts.len() {
+            return Err(StateError::NoMatchingParts);
+        }
+
+        self.compiled = None;
+        self.part_outputs.fill(false);
+        self.prev_outputs.fill(false);
+        for part_type in &mut self.part_types {
+            if let PartType::Timer(buffer) = part_type {
+                buffer.iter_mut().for_each(|v| *v = false);
+            }
+        }
+        for (key, part) in &save.parts {
+            let Some(&i) = indexes.get(key) else {
+                continue;
+            };
+            self.part_outputs[i] = part.on;
+            self.prev_outputs[i] = part.was_on;
+            if let PartType::Timer(buffer) = &mut self.part_types[i] {
+                // the timer might have been changed since, keep its current length
+                for (value, saved) in buffer.iter_mut().zip(&part.timer) {
+                    *value = *saved;
+                }
+            }
+        }
+
+        self.tick = save.tick;
+        self.history.clear();
+        self.breakpoint_hit = None;
+        self.breakpoint_outputs.clear();
+        if let Some(detector) = &mut self.oscillation {
+            detector.reset();
+        }
+        let outputs = self.part_outputs.clone();
+        self.waveform.clear_history(self.tick, &outputs);
+        Ok(missing)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::CanvasSnapshot;
+
+    fn cpu_sim() -> SimState {
+        let project = Path::new(env!("CARGO_MANIFEST_DIR")).join("../sample_projects/6502");
+        let mut canvas = CanvasSnapshot::load(
+            project.join("main_cpu.sml"),
+            Some(project),
+            &mut Vec::new(),
+            Vec::new(),
+        )
+        .expect("sample project should load");
+        SimState::from_canvas_snapshot(&mut canvas, &mut Vec::new())
+    }
+
+    #[test]
+    fn path_keys() {
+        assert_eq!(path_key(&[4, 12]), "4/12");
+        assert_eq!(path_key(&[7]), "7");
+    }
+
+    #[test]
+    fn saved_states_round_trip() {
+        let mut state = cpu_sim();
+        let undriven: Vec<usize> = (0..state.part_inputs.len())
+            .filter(|i| state.part_inputs[*i].is_empty())
+            .collect();
+        for tick in 0..200 {
+            if tick % 7 == 0 {
+                let i = undriven[tick / 7 % undriven.len()];
+                state.set_output(i, !state.part_outputs[i]);
+            }
+            state.tick();
+        }
+
+        let path = std::env::temp_dir().join(format!("smlogic_{}.smstate", std::process::id()));
+        SaveState::capture(&state).save(&path).unwrap();
+        let save = SaveState::load(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        // simulation indexes can be different every time a module is loaded, so compare by
+        // part path
+        let mut restored = cpu_sim();
+        assert_eq!(restored.apply_save_state(&save).unwrap(), 0);
+        for _ in 0..50 {
+            let (expected, got) = (SaveState::capture(&state), SaveState::capture(&restored));
+            assert_eq!(got.tick, expected.tick);
+            assert!(got.parts == expected.parts, "differs at tick {}", got.tick);
+            state.tick();
+            restored.tick();
+        }
+    }
+
+    #[test]
+    fn states_from_other_modules_dont_load() {
+        let mut save = SaveState {
+            tick: 3,
+            parts: BTreeMap::new(),
+        };
+        save.parts.insert(
+            "999999/1".to_string(),
+            SavedPart {
+                on: true,
+                ..Default::default()
+            },
+        );
+        assert!(matches!(
+            cpu_sim().apply_save_state(&save),
+            Err(StateError::NoMatchingParts)
+        ));
+    }
+}
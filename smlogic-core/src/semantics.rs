@@ -0,0 +1,237 @@
+use crate::simulator::SimState;
+
+/// which parts can be forced on or off from outside the simulation (clicks, buses, memory).
+/// it doesnt change how anything ticks, see github_resources/simulation_semantics.md
+#[derive(Clone, Copy, PartialEq)]
+pub enum ForceMode {
+    AnyPart,      // any part can be forced, handy for poking at a circuit
+    SwitchesOnly, // only parts with no inputs can be changed
+}
+
+impl ForceMode {
+    pub const MODES: &[Self] = &[Self::AnyPart, Self::SwitchesOnly];
+
+    pub fn to_label(&self) -> &'static str {
+        match self {
+            ForceMode::AnyPart => "Any part",
+            ForceMode::SwitchesOnly => "Switches only",
+        }
+    }
+}
+
+impl SimState {
+    /// wether `set_output` is allowed to change this part. a driven gate only keeps a forced
+    /// value for one tick anyway, switches only mode just doesnt let you try
+    pub fn can_force(&self, i: usize) -> bool {
+        self.force_mode == ForceMode::AnyPart || self.part_inputs[i].is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::CanvasSnapshot;
+    use crate::compiled::Engine;
+    use crate::connections::Connection;
+    use crate::parts::{Part, PartData, PartType, Port};
+    use emath::pos2;
+
+    fn connect(canvas: &mut CanvasSnapshot, from: u64, to: u64) {
+        canvas.connections.push(Connection {
+            start: Port {
+                part: from,
+                input: false,
+                port_id: None,
+            },
+            end: Port {
+                part: to,
+                input: true,
+                port_id: None,
+            },
+            simulation_index: None,
+        });
+    }
+
+    fn add(canvas: &mut CanvasSnapshot, part: PartType) -> u64 {
+        let y = canvas.next_id as f32 * 10.0;
+        Part::add(part, canvas, pos2(0.0, y), None, &mut Vec::new())
+    }
+
+    /// simulation indexes of `parts`, after building the simulation
+    fn build(canvas: &mut CanvasSnapshot, parts: &[u64], engine: Engine) -> (SimState, Vec<usize>) {
+        let mut state = SimState::from_canvas_snapshot(canvas, &mut Vec::new());
+        state.engine = engine;
+        let indexes = parts
+            .iter()
+            .map(|id| canvas.parts[id].simulation_index.unwrap())
+            .collect();
+        (state, indexes)
+    }
+
+    /// checks a gate with 0 to 3 inputs against `rule(inputs on, inputs)` on every engine,
+    /// for every combination of inputs
+    fn check_gate(gate_type: PartType, rule: impl Fn(usize, usize) -> bool) {
+        for engine in Engine::ENGINES {
+            for inputs in 0..=3 {
+                let mut canvas = CanvasSnapshot::new();
+                let input_ids: Vec<u64> = (0..inputs)
+                    .map(|_| add(&mut canvas, PartType::Input))
+                    .collect();
+                let gate = add(&mut canvas, gate_type.clone());
+                for input in &input_ids {
+                    connect(&mut canvas, *input, gate);
+                }
+                let mut parts = input_ids.clone();
+                parts.push(gate);
+                let (mut state, indexes) = build(&mut canvas, &parts, *engine);
+                let gate = indexes[inputs];
+                if inputs == 0 {
+                    // nothing ever updates it, so it stays off even when the rule says on
+                    for _ in 0..3 {
+                        state.tick();
+                        assert!(!state.part_outputs[gate], "{:?} with no inputs", gate_type);
+                    }
+                    continue;
+                }
+                for combination in 0..1usize << inputs {
+                    for (bit, i) in indexes[..inputs].iter().enumerate() {
+                        state.set_output(*i, combination >> bit & 1 == 1);
+                    }
+                    state.tick();
+                    let on = combination.count_ones() as usize;
+                    assert_eq!(
+                        state.part_outputs[gate],
+                        rule(on, inputs),
+                        "{:?} with {} of {} inputs on, {} engine",
+                        gate_type,
+                        on,
+                        inputs,
+                        engine.to_label()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn and_gate() {
+        check_gate(PartType::And, |on, inputs| on == inputs);
+    }
+
+    #[test]
+    fn or_gate() {
+        check_gate(PartType::Or, |on, _| on > 0);
+    }
+
+    #[test]
+    fn xor_gate() {
+        check_gate(PartType::Xor, |on, _| on % 2 == 1);
+    }
+
+    #[test]
+    fn nand_gate() {
+        check_gate(PartType::Nand, |on, inputs| on != inputs);
+    }
+
+    #[test]
+    fn nor_gate() {
+        check_gate(PartType::Nor, |on, _| on == 0);
+    }
+
+    #[test]
+    fn xnor_gate() {
+        check_gate(PartType::Xnor, |on, _| on % 2 == 0);
+    }
+
+    /// outputs of a timer set to `ticks` ticks for every tick, with its input following `input`
+    fn run_timer(ticks: u8, input: &[bool], engine: Engine) -> Vec<bool> {
+        let mut canvas = CanvasSnapshot::new();
+        let switch = add(&mut canvas, PartType::Input);
+        let timer = add(&mut canvas, PartType::Timer);
+        if let Some(PartData::Timer(timer)) = canvas.parts.get_mut(&timer).map(|p| &mut p.part_data)
+        {
+            timer.ticks = ticks;
+        }
+        connect(&mut canvas, switch, timer);
+        let (mut state, indexes) = build(&mut canvas, &[switch, timer], engine);
+        input
+            .iter()
+            .map(|value| {
+                state.set_output(indexes[0], *value);
+                state.tick();
+                state.part_outputs[indexes[1]]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn timer_delays_by_its_ticks_plus_one() {
+        for engine in Engine::ENGINES {
+            let input = [true, false, false, false, false, false, false];
+            // the input is set before the tick, so a 0 tick timer is on right away like a gate
+            let expected = [true, false, false, false, false, false, false];
+            assert_eq!(run_timer(0, &input, *engine), expected);
+            let expected = [false, false, false, true, false, false, false];
+            assert_eq!(run_timer(3, &input, *engine), expected);
+        }
+    }
+
+    #[test]
+    fn timer_replays_flips_mid_delay() {
+        for engine in Engine::ENGINES {
+            let input = [true, false, true, true, false, false, false, false, false];
+            let expected = [false, false, false, false, true, false, true, true, false];
+            assert_eq!(run_timer(4, &input, *engine), expected);
+        }
+    }
+
+    #[test]
+    fn starting_state() {
+        for engine in Engine::ENGINES {
+            let mut canvas = CanvasSnapshot::new();
+            let lone = add(&mut canvas, PartType::Or);
+            let blocked_nor = add(&mut canvas, PartType::Nor);
+            let switch = add(&mut canvas, PartType::Input);
+            let nor = add(&mut canvas, PartType::Nor);
+            let driven = add(&mut canvas, PartType::Or);
+            connect(&mut canvas, lone, blocked_nor);
+            connect(&mut canvas, switch, nor);
+            connect(&mut canvas, nor, driven);
+            if let Some(PartData::Gate(gate)) =
+                canvas.parts.get_mut(&lone).map(|p| &mut p.part_data)
+            {
+                gate.active = true;
+            }
+            let parts = [lone, blocked_nor, switch, nor, driven];
+            let (mut state, indexes) = build(&mut canvas, &parts, *engine);
+            let mut rows: Vec<Vec<bool>> = Vec::new();
+            for _ in 0..3 {
+                rows.push(indexes.iter().map(|i| state.part_outputs[*i]).collect());
+                state.tick();
+            }
+            // a part that starts on with no inputs stays on, a NOR with its inputs off turns on
+            // at tick 1 and whatever it drives a tick after that
+            let expected = vec![
+                vec![true, false, false, false, false],
+                vec![true, false, false, true, false],
+                vec![true, false, false, true, true],
+            ];
+            assert_eq!(rows, expected, "{} engine", engine.to_label());
+        }
+    }
+
+    #[test]
+    fn switches_only_can_force_undriven_parts() {
+        let mut canvas = CanvasSnapshot::new();
+        let switch = add(&mut canvas, PartType::Input);
+        let gate = add(&mut canvas, PartType::And);
+        connect(&mut canvas, switch, gate);
+        let (mut state, indexes) = build(&mut canvas, &[switch, gate], Engine::Simple);
+        assert!(state.can_force(indexes[0]) && state.can_force(indexes[1]));
+        state.force_mode = ForceMode::SwitchesOnly;
+        assert!(state.can_force(indexes[0]));
+        assert!(!state.can_force(indexes[1]));
+        state.set_output(indexes[1], true);
+        assert!(!state.part_outputs[indexes[1]]);
+    }
+}
@@ -0,0 +1,623 @@
+use crate::breakpoints::Breakpoint;
+use crate::canvas::CanvasSnapshot;
+use crate::compiled::{CompiledSim, Engine};
+use crate::history::History;
+use crate::oscillation::OscillationDetector;
+use crate::parts::{GateType, PartData, Port};
+use crate::semantics::ForceMode;
+use crate::stimulus::Stimulus;
+use crate::waveform::Waveform;
+use ecolor::Color32;
+use emath::Pos2;
+use parking_lot::Mutex;
+use rayon::prelude::*;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// handing a tick to the thread pool costs about 15us (`smlogic bench --display 8x6 --threads 4`
+// with this set to 1 goes from 28.7k tps on 1 thread to 20.0k on 4) and ticking a part costs
+// 40-65ns (510 tps for the 29k parts of `--display 48x36`), so at 4000 parts a thread the
+// handoff is under 10% of the work. measured on a single core, so that 15us is on the high side
+pub const MIN_PARTS_PER_THREAD: usize = 4_000;
+pub const BATCH_SIZE: usize = 64; // how many operations are done per loop, so i dont have to lock the
+// variable as much. might change this to be dynamic later tho.
+
+#[derive(Clone)]
+pub enum PartType {
+    And,
+    Or,
+    Xor,
+    Nand,
+    Nor,
+    Xnor,
+    Timer(VecDeque<bool>),
+}
+
+pub struct ImportantGate {
+    pub label: String,
+    pub color: Color32,
+    pub tree: String,
+    pub simulation_index: usize,
+    pub gate_type_label: String,
+}
+
+pub struct SimSnapshot {
+    pub outputs: Vec<bool>,
+    pub tick: u64,
+    pub running: bool,
+    pub target_spt: Option<Duration>,
+    pub breakpoint_hit: Option<Breakpoint>,
+    pub oldest_saved_tick: Option<u64>, // how far back it can be rewound
+}
+
+pub struct SimState {
+    pub running: bool,
+    pub step: bool,
+    pub kill_thread: bool,
+    pub target_spt: Option<Duration>, // 1 / tps
+    pub tick: u64,
+    pub part_types: Vec<PartType>,
+    pub part_outputs: Vec<bool>,
+    pub prev_outputs: Vec<bool>,
+    pub part_inputs: Vec<Vec<usize>>,
+    pub waveform: Waveform,
+    pub breakpoints: Vec<Breakpoint>,
+    pub breakpoint_hit: Option<usize>, // index into breakpoints, cleared when ticking again
+    // outputs at the last breakpoint check, empty when there isnt one to compare against
+    pub breakpoint_outputs: Vec<bool>,
+    pub history: History,
+    pub part_paths: Vec<Vec<u64>>, // see `RawCanvasData::part_paths`
+    pub port_paths: HashMap<Vec<u64>, usize>, // see `RawCanvasData::port_paths`
+    pub engine: Engine,
+    pub threads: usize, // threads the simple engine splits ticks over, 1 turns it off
+    // built on the first compiled tick, thrown away whenever outputs are changed from outside
+    pub compiled: Option<Box<CompiledSim>>,
+    pub oscillation: Option<OscillationDetector>, // off unless turned on, hashes every tick
+    pub stimuli: Vec<(usize, Stimulus)>,          // (simulation index, what drives it)
+    pub force_mode: ForceMode,
+    pool: Option<rayon::ThreadPool>, // worker threads for the simple engine, see `threads`
+}
+
+impl SimState {
+    pub fn tick(&mut self) {
+        if self.engine != Engine::Simple {
+            self.tick_compiled();
+            return;
+        }
+        self.compiled = None;
+        self.tick += 1;
+        std::mem::swap(&mut self.part_outputs, &mut self.prev_outputs);
+        let threads = self.effective_threads();
+        // the pool sticks around between ticks, only rebuilt when the thread count changes
+        if threads > 1
+            && self
+                .pool
+                .as_ref()
+                .is_none_or(|p| p.current_num_threads() != threads)
+        {
+            self.pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .ok();
+        }
+        let pool = match &self.pool {
+            Some(pool) if threads > 1 => pool,
+            _ => {
+                tick_parts(
+                    &mut self.part_types,
+                    &self.part_inputs,
+                    &self.prev_outputs,
+                    &mut self.part_outputs,
+                );
+                return;
+            }
+        };
+        // every part only reads prev_outputs, so each thread gets its own slice of parts
+        let chunk = self.part_types.len().div_ceil(threads);
+        let prev_outputs = &self.prev_outputs;
+        let part_inputs = &self.part_inputs;
+        let part_types = &mut self.part_types;
+        let part_outputs = &mut self.part_outputs;
+        pool.install(|| {
+            part_types
+                .par_chunks_mut(chunk)
+                .zip(part_inputs.par_chunks(chunk))
+                .zip(part_outputs.par_chunks_mut(chunk))
+                .for_each(|((part_types, part_inputs), part_outputs)| {
+                    tick_parts(part_types, part_inputs, prev_outputs, part_outputs)
+                });
+        });
+    }
+
+    /// how many threads a simple engine tick actually uses, `threads` is only a limit since
+    /// every thread needs at least `MIN_PARTS_PER_THREAD` parts
+    pub fn effective_threads(&self) -> usize {
+        effective_threads(self.threads, self.part_types.len())
+    }
+
+    pub fn record_waveform(&mut self) {
+        if !self.waveform.traces.is_empty() {
+            self.waveform.record(&self.part_outputs);
+        }
+    }
+
+    /// forces a parts output, used for toggling inputs. sets the previous output too so the
+    /// change is seen by the very next tick. does nothing if `can_force` says no
+    pub fn set_output(&mut self, i: usize, value: bool) {
+        if !self.can_force(i) {
+            return;
+        }
+        if let Some(compiled) = &mut self.compiled {
+            compiled.set_output(i, value);
+        }
+        self.part_outputs[i] = value;
+        self.prev_outputs[i] = value;
+        // so the waveform shows the new value from this tick on
+        for trace in self.waveform.traces.iter_mut().filter(|t| t.index == i) {
+            if let Some(last) = trace.values.back_mut() {
+                *last = value;
+            }
+        }
+    }
+
+    /// every part path with the simulation index that shows its value, including io parts
+    /// inside modules
+    pub fn path_indexes(&self) -> HashMap<Vec<u64>, usize> {
+        let mut indexes = self.port_paths.clone();
+        for (i, path) in self.part_paths.iter().enumerate() {
+            indexes.insert(path.clone(), i);
+        }
+        indexes
+    }
+
+    pub fn from_canvas_snapshot(
+        canvas: &mut CanvasSnapshot,
+        important_gates: &mut Vec<ImportantGate>,
+    ) -> Self {
+        let RawCanvasData {
+            parts: part_types,
+            active,
+            connections,
+            id_remap,
+            port_sim_map,
+            important_trees,
+            part_paths,
+            port_paths,
+            ..
+        } = get_canvas_raw_data(canvas.clone(), true, &Vec::new());
+        for (original_id, new_i) in &id_remap {
+            if let Some(part) = canvas.parts.get_mut(original_id) {
+                part.simulation_index = Some(*new_i);
+            }
+        }
+        for connection in canvas.connections.iter_mut() {
+            connection.simulation_index = port_sim_map
+                .get(&(connection.start.part, connection.start.port_id))
+                .copied();
+        }
+        for (simulation_index, tree, label, color, gate_type_label) in important_trees {
+            if !tree.is_empty() {
+                important_gates.push(ImportantGate {
+                    label,
+                    color,
+                    tree,
+                    simulation_index,
+                    gate_type_label,
+                });
+            }
+        }
+
+        let mut part_inputs: Vec<Vec<usize>> = vec![Vec::new(); part_types.len()];
+        for connection in connections {
+            part_inputs[connection.1].push(connection.0);
+        }
+
+        Self {
+            running: false,
+            step: false,
+            kill_thread: false,
+            target_spt: Some(Duration::from_secs_f32(0.025)), // 40 tps
+            tick: 0,
+            part_types: part_types.clone(),
+            part_outputs: active.clone(),
+            prev_outputs: active,
+            part_inputs,
+            waveform: Waveform::default(),
+            breakpoints: Vec::new(),
+            breakpoint_hit: None,
+            breakpoint_outputs: Vec::new(),
+            history: History::default(),
+            part_paths,
+            port_paths,
+            engine: Engine::Simple,
+            threads: 1,
+            compiled: None,
+            oscillation: None,
+            stimuli: Vec::new(),
+            force_mode: ForceMode::AnyPart,
+            pool: None,
+        }
+    }
+}
+
+/// how many threads `threads` turns into for a circuit with `parts` parts
+pub fn effective_threads(threads: usize, parts: usize) -> usize {
+    threads.min(parts / MIN_PARTS_PER_THREAD).max(1)
+}
+
+/// the simple engine, works out the next output of a range of parts. `part_types`,
+/// `part_inputs` and `part_outputs` are all the same range, `prev_outputs` is every part.
+fn tick_parts(
+    part_types: &mut [PartType],
+    part_inputs: &[Vec<usize>],
+    prev_outputs: &[bool],
+    part_outputs: &mut [bool],
+) {
+    for i in 0..part_types.len() {
+        if !part_inputs[i].is_empty() {
+            let input_idxs = &part_inputs[i];
+            match &mut part_types[i] {
+                PartType::And => {
+                    part_outputs[i] = input_idxs.iter().all(|&idx| prev_outputs[idx]);
+                }
+                PartType::Or => {
+                    part_outputs[i] = input_idxs.iter().any(|&idx| prev_outputs[idx]);
+                }
+                PartType::Xor => {
+                    part_outputs[i] =
+                        input_idxs.iter().filter(|&&idx| prev_outputs[idx]).count() % 2 == 1;
+                }
+                PartType::Nand => {
+                    part_outputs[i] = !input_idxs.iter().all(|&idx| prev_outputs[idx]);
+                }
+                PartType::Nor => {
+                    part_outputs[i] = !input_idxs.iter().any(|&idx| prev_outputs[idx]);
+                }
+                PartType::Xnor => {
+                    part_outputs[i] =
+                        !input_idxs.iter().filter(|&&idx| prev_outputs[idx]).count() % 2 == 1;
+                }
+                PartType::Timer(buffer) => {
+                    let input = input_idxs
+                        .first()
+                        .map(|&idx| prev_outputs[idx])
+                        .unwrap_or(false);
+                    if buffer.is_empty() {
+                        // 0 tick timer doesnt need any buffer stuff, just pass it through
+                        part_outputs[i] = input;
+                    } else {
+                        let out = buffer.pop_back().unwrap_or(false);
+                        buffer.push_front(input);
+                        part_outputs[i] = out;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// a canvas flattened down to plain gates, everything is indexed by simulation index
+pub struct RawCanvasData {
+    pub parts: Vec<PartType>,
+    pub colors: Vec<Color32>,
+    pub positions: Vec<Pos2>,
+    pub active: Vec<bool>, // what each part starts as, see `Gate::active`
+    pub connections: Vec<(usize, usize)>,
+    pub id_remap: HashMap<u64, usize>,
+    pub tunnel_connections: HashMap<u64, Vec<usize>>,
+    pub io_parts: Vec<usize>, // only should have stuff in it if top level
+    pub important_parts: Vec<usize>,
+    pub port_sim_map: HashMap<(u64, Option<u64>), usize>, // (part_id, port_id) -> sim index
+    pub important_trees: Vec<(usize, String, String, Color32, String)>, // (index, tree, label, color, gate_type_label)
+    pub part_paths: Vec<Vec<u64>>, // module ids down to the parts own id, same between runs
+    // io parts inside modules arent simulated, so this maps their path to whatever drives them
+    pub port_paths: HashMap<Vec<u64>, usize>,
+}
+
+// this was fucking torture to make istg lost my mind
+// also had alot of issues so i js made claude fix them (this function was too much for me lmao)
+pub fn get_canvas_raw_data(
+    canvas: CanvasSnapshot,
+    top_level: bool,      // wether it is the main canvas or not (not sub modules)
+    ancestors: &[String], // labels of the modules we're nested inside of, outermost first
+) -> RawCanvasData {
+    let mut id_remap: HashMap<u64, usize> = HashMap::new();
+    let mut part_output: Vec<PartType> = Vec::new();
+    let mut color_output: Vec<Color32> = Vec::new();
+    let mut pos_output: Vec<Pos2> = Vec::new();
+    let mut active_output: Vec<bool> = Vec::new();
+    let mut path_output: Vec<Vec<u64>> = Vec::new();
+    let mut port_paths: HashMap<Vec<u64>, usize> = HashMap::new();
+    let mut connection_output: Vec<(usize, usize)> = Vec::new();
+
+    // top level only
+    let mut io_parts: Vec<usize> = Vec::new();
+
+    let mut important_parts: Vec<usize> = Vec::new();
+    let mut important_trees: Vec<(usize, String, String, Color32, String)> = Vec::new();
+    let mut tunnel_connections: HashMap<u64, Vec<usize>> = HashMap::new();
+    let mut sub_tunnel_connections: HashMap<u64, HashMap<u64, Vec<usize>>> = HashMap::new();
+
+    for (part_id, part) in &canvas.parts {
+        match part.part_data.clone() {
+            PartData::Gate(gate) => {
+                let new_i = part_output.len();
+                part_output.push(match gate.gate_type {
+                    GateType::And => PartType::And,
+                    GateType::Or => PartType::Or,
+                    GateType::Xor => PartType::Xor,
+                    GateType::Nand => PartType::Nand,
+                    GateType::Nor => PartType::Nor,
+                    GateType::Xnor => PartType::Xnor,
+                });
+                color_output.push(part.color);
+                pos_output.push(part.pos);
+                active_output.push(gate.active);
+                path_output.push(vec![*part_id]);
+                id_remap.insert(*part_id, new_i);
+                if gate.important {
+                    important_parts.push(new_i);
+                    important_trees.push((
+                        new_i,
+                        ancestors.join(" > "),
+                        part.label.clone(),
+                        part.color,
+                        gate.gate_type.to_label(),
+                    ));
+                }
+            }
+            PartData::Timer(timer) => {
+                let new_i = part_output.len();
+                let active = timer.active;
+                let ticks = timer.get_ticks();
+                part_output.push(PartType::Timer(VecDeque::from(vec![false; ticks])));
+                color_output.push(part.color);
+                pos_output.push(part.pos);
+                active_output.push(active);
+                path_output.push(vec![*part_id]);
+                id_remap.insert(*part_id, new_i);
+            }
+            PartData::Module(module) => {
+                let mut sub_ancestors = ancestors.to_vec();
+                sub_ancestors.push(part.label.clone());
+                let RawCanvasData {
+                    parts: module_parts,
+                    colors,
+                    positions,
+                    active,
+                    connections: module_connections,
+                    tunnel_connections: module_tunnel_connections,
+                    important_parts: important,
+                    important_trees: important_trees_sub,
+                    part_paths,
+                    port_paths: module_port_paths,
+                    ..
+                } = get_canvas_raw_data(module.canvas_snapshot, false, &sub_ancestors);
+                let offset = part_output.len();
+                part_output.extend(module_parts);
+                color_output.extend(colors);
+                pos_output.extend(positions);
+                active_output.extend(active);
+                path_output.extend(part_paths.into_iter().map(|path| {
+                    let mut full_path = vec![*part_id];
+                    full_path.extend(path);
+                    full_path
+                }));
+                port_paths.extend(module_port_paths.into_iter().map(|(path, i)| {
+                    let mut full_path = vec![*part_id];
+                    full_path.extend(path);
+                    (full_path, i + offset)
+                }));
+                important_parts.extend(important.iter().map(|a| a + offset));
+                important_trees.extend(important_trees_sub.into_iter().map(
+                    |(a, tree, label, color, gate_type_label)| {
+                        (a + offset, tree, label, color, gate_type_label)
+                    },
+                ));
+
+                connection_output.extend(
+                    module_connections
+                        .iter()
+                        .map(|(a, b)| (a + offset, b + offset)),
+                );
+
+                let remapped_tunnel_connections: HashMap<_, _> = module_tunnel_connections
+                    .iter()
+                    .map(|(&k, v)| (k, v.iter().map(|&x| x + offset).collect::<Vec<_>>()))
+                    .collect();
+
+                sub_tunnel_connections.insert(*part_id, remapped_tunnel_connections);
+            }
+            PartData::IO(_io) if top_level => {
+                let new_i = part_output.len();
+                part_output.push(PartType::And);
+                color_output.push(part.color);
+                pos_output.push(part.pos);
+                active_output.push(false);
+                path_output.push(vec![*part_id]);
+                id_remap.insert(*part_id, new_i);
+                io_parts.push(new_i);
+            }
+            _ => {}
+        }
+    }
+
+    let resolve = |port: &Port| -> Vec<usize> {
+        if let Some(&new_id) = id_remap.get(&port.part) {
+            vec![new_id]
+        } else if let Some(module) = sub_tunnel_connections.get(&port.part) {
+            port.port_id
+                .and_then(|pid| module.get(&pid))
+                .cloned()
+                .unwrap_or_default()
+        } else {
+            vec![]
+        }
+    };
+
+    for connection in canvas.connections {
+        let start_is_io = canvas
+            .parts
+            .get(&connection.start.part)
+            .is_some_and(|p| matches!(&p.part_data, PartData::IO(_)));
+        let end_is_io = canvas
+            .parts
+            .get(&connection.end.part)
+            .is_some_and(|p| matches!(&p.part_data, PartData::IO(_)));
+
+        if !top_level && start_is_io {
+            // sub-module IO input fans out to multiple internal gates — collect all of them
+            for new_id in resolve(&connection.end) {
+                tunnel_connections
+                    .entry(connection.start.part)
+                    .or_default()
+                    .push(new_id);
+            }
+        } else if !top_level && end_is_io {
+            let starts = resolve(&connection.start);
+            if let Some(&start) = starts.first() {
+                port_paths.insert(vec![connection.end.part], start);
+            }
+            for new_id in starts {
+                tunnel_connections
+                    .entry(connection.end.part)
+                    .or_default()
+                    .push(new_id);
+            }
+        } else {
+            // top-level IO parts are real AND gates in id_remap, so resolve() finds them normally
+            let starts = resolve(&connection.start);
+            let ends = resolve(&connection.end);
+            // the module input this wire goes into
+            if let (Some(&start), Some(port_id)) = (starts.first(), connection.end.port_id)
+                && sub_tunnel_connections.contains_key(&connection.end.part)
+            {
+                port_paths.insert(vec![connection.end.part, port_id], start);
+            }
+            for &start_port in &starts {
+                for &end_port in &ends {
+                    connection_output.push((start_port, end_port));
+                }
+            }
+        }
+    }
+
+    let mut port_sim_map: HashMap<(u64, Option<u64>), usize> = HashMap::new();
+    for (&part_id, &idx) in &id_remap {
+        port_sim_map.insert((part_id, None), idx);
+    }
+    for (&module_id, inner_map) in &sub_tunnel_connections {
+        for (&inner_io_id, indices) in inner_map {
+            if let Some(&idx) = indices.first() {
+                port_sim_map.insert((module_id, Some(inner_io_id)), idx);
+            }
+        }
+    }
+
+    RawCanvasData {
+        parts: part_output,
+        colors: color_output,
+        positions: pos_output,
+        active: active_output,
+        connections: connection_output,
+        id_remap,
+        tunnel_connections,
+        io_parts,
+        important_parts,
+        port_sim_map,
+        important_trees,
+        part_paths: path_output,
+        port_paths,
+    }
+}
+
+pub fn main_loop(sim_state: Arc<Mutex<SimState>>, sim_snapshot: Arc<Mutex<SimSnapshot>>) {
+    let mut last_tick = Instant::now();
+    loop {
+        let (running, step, spt, kill) = {
+            let mut state = sim_state.lock();
+            let batch = if state.target_spt.is_none() {
+                BATCH_SIZE
+            } else {
+                1
+            };
+            if state.running {
+                state.breakpoint_hit = None;
+                for _ in 0..batch {
+                    state.save_history();
+                    state.tick();
+                    state.apply_stimuli();
+                    state.record_waveform();
+                    state.check_oscillation();
+                    if state.check_breakpoints() {
+                        break;
+                    }
+                }
+            } else if state.step {
+                state.breakpoint_hit = None;
+                state.save_history();
+                state.tick();
+                state.apply_stimuli();
+                state.record_waveform();
+                state.check_oscillation();
+                state.check_breakpoints();
+                state.step = false;
+            }
+            let mut snap = sim_snapshot.lock();
+            snap.outputs.clone_from(&state.part_outputs);
+            snap.tick = state.tick;
+            snap.running = state.running;
+            snap.target_spt = state.target_spt;
+            snap.breakpoint_hit = state
+                .breakpoint_hit
+                .and_then(|i| state.breakpoints.get(i).cloned());
+            snap.oldest_saved_tick = state.history.oldest_tick();
+            drop(snap);
+            (
+                state.running,
+                state.step,
+                state.target_spt,
+                state.kill_thread,
+            )
+        };
+
+        if kill {
+            break;
+        }
+
+        if running {
+            if let Some(spt) = spt {
+                let elapsed = last_tick.elapsed();
+                if elapsed < spt {
+                    std::thread::sleep(spt - elapsed);
+                }
+                last_tick = Instant::now();
+            }
+        } else if !running && !step {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+}
+
+pub fn start_thread(
+    canvas: &mut CanvasSnapshot,
+    important_gates: &mut Vec<ImportantGate>,
+) -> (Arc<Mutex<SimState>>, Arc<Mutex<SimSnapshot>>) {
+    let sim_state = Arc::new(Mutex::new(SimState::from_canvas_snapshot(
+        canvas,
+        important_gates,
+    )));
+    let sim_state_thread = Arc::clone(&sim_state);
+    let sim_snapshot = Arc::new(Mutex::new(SimSnapshot {
+        outputs: Vec::new(),
+        tick: 0,
+        running: false,
+        target_spt: None,
+        breakpoint_hit: None,
+        oldest_saved_tick: None,
+    }));
+    let sim_snapshot_thread = Arc::clone(&sim_snapshot);
+    std::thread::spawn(move || main_loop(sim_state_thread, sim_snapshot_thread));
+    (sim_state, sim_snapshot)
+}
@@ -0,0 +1,128 @@
+use crate::simulator::SimState;
+
+/// something that drives an input by itself while simulating, so clocks dont have to be built
+/// out of timers just for testing
+#[derive(Clone, Copy, PartialEq)]
+pub enum Stimulus {
+    Clock { period: u64, duty: u64 }, // on for `duty` ticks out of every `period`
+    Pulse { start: u64, length: u64 },
+    Noise { seed: u64 },
+}
+
+impl Stimulus {
+    pub const KINDS: &[Self] = &[
+        Self::Clock {
+            period: 10,
+            duty: 5,
+        },
+        Self::Pulse {
+            start: 0,
+            length: 1,
+        },
+        Self::Noise { seed: 0 },
+    ];
+
+    pub fn to_label(&self) -> &'static str {
+        match self {
+            Stimulus::Clock { .. } => "Clock",
+            Stimulus::Pulse { .. } => "Pulse",
+            Stimulus::Noise { .. } => "Noise",
+        }
+    }
+
+    /// what the input should be at a tick. only depends on the tick so rewinding replays it
+    pub fn value(&self, tick: u64) -> bool {
+        match *self {
+            Stimulus::Clock { period, duty } => tick % period.max(1) < duty,
+            Stimulus::Pulse { start, length } => tick >= start && tick - start < length,
+            Stimulus::Noise { seed } => splitmix(splitmix(seed) ^ tick) & 1 == 1,
+        }
+    }
+}
+
+fn splitmix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e3779b97f4a7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+impl SimState {
+    /// sets every input with a stimulus to its value for the current tick, called after every
+    /// tick
+    pub fn apply_stimuli(&mut self) {
+        for i in 0..self.stimuli.len() {
+            let (index, stimulus) = self.stimuli[i];
+            self.apply_stimulus(index, stimulus);
+        }
+    }
+
+    fn apply_stimulus(&mut self, index: usize, stimulus: Stimulus) {
+        let value = stimulus.value(self.tick);
+        if self.part_outputs[index] != value {
+            self.set_output(index, value);
+        }
+    }
+
+    /// gives an input a stimulus, or takes it away with `None`. the input changes straight
+    /// away instead of after the next tick, so it already has the right value on tick 0
+    pub fn set_stimulus(&mut self, index: usize, stimulus: Option<Stimulus>) {
+        let position = self.stimuli.iter().position(|(i, _)| *i == index);
+        match (position, stimulus) {
+            (Some(p), Some(stimulus)) => self.stimuli[p].1 = stimulus,
+            (Some(p), None) => {
+                self.stimuli.remove(p);
+            }
+            (None, Some(stimulus)) => self.stimuli.push((index, stimulus)),
+            (None, None) => {}
+        }
+        if let Some(stimulus) = stimulus {
+            self.apply_stimulus(index, stimulus);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::CanvasSnapshot;
+    use crate::parts::{Part, PartType};
+    use emath::Pos2;
+
+    #[test]
+    fn stimuli_apply_as_soon_as_they_are_set() {
+        let mut canvas = CanvasSnapshot::new();
+        let input = Part::add(
+            PartType::Input,
+            &mut canvas,
+            Pos2::ZERO,
+            None,
+            &mut Vec::new(),
+        );
+        let mut state = SimState::from_canvas_snapshot(&mut canvas, &mut Vec::new());
+        let i = canvas.parts[&input].simulation_index.unwrap();
+
+        let clock = Stimulus::Clock { period: 4, duty: 2 };
+        state.set_stimulus(i, Some(clock));
+        let mut values = vec![state.part_outputs[i]];
+        for _ in 0..7 {
+            state.tick();
+            state.apply_stimuli();
+            values.push(state.part_outputs[i]);
+        }
+        let expected: Vec<bool> = (0..8).map(|tick| clock.value(tick)).collect();
+        assert_eq!(values, expected);
+
+        // changing it mid run takes effect right away too
+        state.set_stimulus(
+            i,
+            Some(Stimulus::Pulse {
+                start: 0,
+                length: 100,
+            }),
+        );
+        assert!(state.part_outputs[i]);
+        state.set_stimulus(i, None);
+        assert!(state.stimuli.is_empty());
+    }
+}
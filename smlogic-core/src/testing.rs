@@ -0,0 +1,256 @@
+use crate::canvas::CanvasSnapshot;
+use crate::error::VectorError;
+use crate::headless::{HeadlessSim, parse_bool};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TestStep {
+    Set { label: String, value: bool },
+    Wait(u64),
+    Expect { label: String, value: bool },
+}
+
+/// one `test` block of a test file, every case starts from a fresh simulation
+#[derive(Clone, Debug, PartialEq)]
+pub struct TestCase {
+    pub name: String,
+    pub steps: Vec<(usize, TestStep)>, // (line, step)
+}
+
+/// an `expect` that didnt match
+#[derive(Clone, Debug, PartialEq)]
+pub struct TestFailure {
+    pub line: usize,
+    pub tick: u64,
+    pub label: String,
+    pub expected: bool,
+}
+
+impl fmt::Display for TestFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, tick {}: \"{}\" should be {} but was {}",
+            self.line, self.tick, self.label, self.expected as u8, !self.expected as u8
+        )
+    }
+}
+
+pub struct TestResult {
+    pub name: String,
+    pub failures: Vec<TestFailure>,
+    pub error: Option<VectorError>, // the case couldnt run at all, ie a label that doesnt exist
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty() && self.error.is_none()
+    }
+}
+
+/// where the tests for a module live, `adder.sml` -> `adder.sml.test`
+pub fn test_file_path(module: &Path) -> PathBuf {
+    let mut path = module.as_os_str().to_owned();
+    path.push(".test");
+    PathBuf::from(path)
+}
+
+/// parses a test file. looks like:
+/// ```text
+/// test 1 + 1
+/// set input 1 = 1
+/// set input 2 = 1
+/// wait 4
+/// expect output = 0
+/// expect carry = 1
+/// ```
+/// anything after a `#` is ignored, and steps before the first `test` line go in an unnamed case.
+pub fn parse_test_file(contents: &str) -> Result<Vec<TestCase>, VectorError> {
+    let mut cases: Vec<TestCase> = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let parse_error = |message: &str| VectorError::Parse {
+            line: line_number,
+            message: message.to_string(),
+        };
+        let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let rest = rest.trim();
+
+        if command == "test" {
+            cases.push(TestCase {
+                name: rest.to_string(),
+                steps: Vec::new(),
+            });
+            continue;
+        }
+        let step = match command {
+            "set" | "expect" => {
+                let (label, value) = rest
+                    .rsplit_once('=')
+                    .ok_or_else(|| parse_error("expected `label = value`"))?;
+                let label = label.trim().to_string();
+                let value = parse_bool(value.trim()).ok_or_else(|| parse_error("invalid value"))?;
+                if command == "set" {
+                    TestStep::Set { label, value }
+                } else {
+                    TestStep::Expect { label, value }
+                }
+            }
+            "wait" => TestStep::Wait(
+                rest.parse()
+                    .map_err(|_| parse_error("invalid tick count"))?,
+            ),
+            _ => return Err(parse_error(&format!("unknown command `{}`", command))),
+        };
+        if cases.is_empty() {
+            cases.push(TestCase {
+                name: String::new(),
+                steps: Vec::new(),
+            });
+        }
+        if let Some(case) = cases.last_mut() {
+            case.steps.push((line_number, step));
+        }
+    }
+    Ok(cases)
+}
+
+pub fn run_test_case(canvas: &CanvasSnapshot, case: &TestCase) -> TestResult {
+    let mut result = TestResult {
+        name: case.name.clone(),
+        failures: Vec::new(),
+        error: None,
+    };
+    let mut sim = HeadlessSim::new(canvas);
+    for (line, step) in &case.steps {
+        let step_result = match step {
+            TestStep::Set { label, value } => sim.set_input(label, *value),
+            TestStep::Wait(ticks) => {
+                for _ in 0..*ticks {
+                    sim.state.tick();
+                }
+                Ok(())
+            }
+            TestStep::Expect { label, value } => sim.output(label).map(|values| {
+                if values.iter().any(|v| v != value) {
+                    result.failures.push(TestFailure {
+                        line: *line,
+                        tick: sim.state.tick,
+                        label: label.clone(),
+                        expected: *value,
+                    });
+                }
+            }),
+        };
+        if let Err(e) = step_result {
+            result.error = Some(VectorError::Parse {
+                line: *line,
+                message: e.to_string(),
+            });
+            break;
+        }
+    }
+    result
+}
+
+pub fn run_tests(canvas: &CanvasSnapshot, cases: &[TestCase]) -> Vec<TestResult> {
+    cases
+        .iter()
+        .map(|case| run_test_case(canvas, case))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(label: &str, value: bool) -> TestStep {
+        TestStep::Set {
+            label: label.to_string(),
+            value,
+        }
+    }
+
+    fn expect(label: &str, value: bool) -> TestStep {
+        TestStep::Expect {
+            label: label.to_string(),
+            value,
+        }
+    }
+
+    #[test]
+    fn parses_cases() {
+        let contents = "set enable = 1 # before any test\n\
+            \n\
+            test 1 + 1 # adds\n\
+            set a = b = 1\n\
+            wait 4\n\
+            expect sum = off\n\
+            # test commented out\n\
+            test\n\
+            expect carry = on\n";
+        let cases = parse_test_file(contents).unwrap();
+        assert_eq!(
+            cases,
+            vec![
+                TestCase {
+                    name: String::new(),
+                    steps: vec![(1, set("enable", true))],
+                },
+                TestCase {
+                    name: "1 + 1".to_string(),
+                    steps: vec![
+                        (4, set("a = b", true)),
+                        (5, TestStep::Wait(4)),
+                        (6, expect("sum", false)),
+                    ],
+                },
+                TestCase {
+                    name: String::new(),
+                    steps: vec![(9, expect("carry", true))],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_file_errors_have_line_numbers() {
+        for (contents, bad_line) in [
+            ("test a\nset a 1", 2),
+            ("test a\n\nwait soon", 3),
+            ("expect a = 2", 1),
+            ("test a\nprint a", 2),
+        ] {
+            match parse_test_file(contents) {
+                Err(VectorError::Parse { line, .. }) => assert_eq!(line, bad_line, "{}", contents),
+                _ => panic!("{:?} should not parse", contents),
+            }
+        }
+    }
+
+    #[test]
+    fn test_files_sit_next_to_their_module() {
+        assert_eq!(
+            test_file_path(Path::new("project/adder.sml")),
+            PathBuf::from("project/adder.sml.test")
+        );
+    }
+
+    #[test]
+    fn semantics_tests_pass() {
+        let project = Path::new(env!("CARGO_MANIFEST_DIR")).join("../sample_projects/test");
+        let module = project.join("semantics.sml");
+        let contents = std::fs::read_to_string(test_file_path(&module)).unwrap();
+        let cases = parse_test_file(&contents).unwrap();
+        let canvas = CanvasSnapshot::load(module, Some(project), &mut Vec::new(), Vec::new())
+            .expect("sample project should load");
+        for result in run_tests(&canvas, &cases) {
+            assert!(result.passed(), "{} failed", result.name);
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use crate::waveform::{Trace, Waveform};
+use std::fmt::Write;
+
+pub const MS_PER_TICK: u64 = 25; // scrap mechanic runs at 40 ticks per second
+
+/// a module scope in the vcd header, nested the same way the modules are
+struct Scope {
+    name: String,
+    vars: Vec<(String, String)>, // (name, id code)
+    children: Vec<Scope>,
+}
+
+impl Scope {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            vars: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    fn child(&mut self, name: &str) -> &mut Scope {
+        let name = vcd_name(name);
+        let i = match self.children.iter().position(|c| c.name == name) {
+            Some(i) => i,
+            None => {
+                self.children.push(Scope::new(name));
+                self.children.len() - 1
+            }
+        };
+        &mut self.children[i]
+    }
+
+    /// adds a var, labels dont have to be unique so repeats get `_2`, `_3`... on the end
+    fn add_var(&mut self, name: &str, id: String) {
+        let base = vcd_name(name);
+        let mut name = base.clone();
+        let mut n = 1;
+        while self.vars.iter().any(|(v, _)| *v == name) {
+            n += 1;
+            name = format!("{}_{}", base, n);
+        }
+        self.vars.push((name, id));
+    }
+
+    fn write(&self, out: &mut String) {
+        let _ = writeln!(out, "$scope module {} $end", self.name);
+        for (name, id) in &self.vars {
+            let _ = writeln!(out, "$var wire 1 {} {} $end", id, name);
+        }
+        for child in &self.children {
+            child.write(out);
+        }
+        out.push_str("$upscope $end\n");
+    }
+}
+
+/// vcd names cant have whitespace in them
+fn vcd_name(name: &str) -> String {
+    let name: String = name
+        .trim()
+        .chars()
+        .map(|c| if c.is_whitespace() { '_' } else { c })
+        .collect();
+    if name.is_empty() {
+        "unnamed".to_string()
+    } else {
+        name
+    }
+}
+
+/// short identifier for a signal, made of the printable ascii chars `!` to `~`
+fn id_code(mut i: usize) -> String {
+    let mut id = String::new();
+    loop {
+        id.push((b'!' + (i % 94) as u8) as char);
+        i /= 94;
+        if i == 0 {
+            return id;
+        }
+        i -= 1;
+    }
+}
+
+fn value_char(value: Option<bool>) -> char {
+    match value {
+        Some(true) => '1',
+        Some(false) => '0',
+        None => 'x', // not recorded yet
+    }
+}
+
+/// turns the recorded traces into a value change dump for gtkwave and friends. signals are
+/// scoped by the modules theyre nested in, under a top scope called `top`. theres no `$date`
+/// so dumps of the same run are identical and can be diffed.
+pub fn write_vcd(waveform: &Waveform, top: &str) -> String {
+    let traces: Vec<&Trace> = waveform.traces.iter().collect();
+    let mut root = Scope::new(vcd_name(top));
+    for (i, trace) in traces.iter().enumerate() {
+        let mut scope = &mut root;
+        for module in trace.tree.split(" > ").filter(|m| !m.is_empty()) {
+            scope = scope.child(module);
+        }
+        scope.add_var(&trace.name, id_code(i));
+    }
+
+    let mut out = String::new();
+    out.push_str("$version SMLogic $end\n");
+    let _ = writeln!(
+        out,
+        "$comment 1 scrap mechanic tick = {} ms $end",
+        MS_PER_TICK
+    );
+    out.push_str("$timescale 1 ms $end\n");
+    root.write(&mut out);
+    out.push_str("$enddefinitions $end\n");
+
+    let Some((start, end)) = waveform.tick_range() else {
+        return out;
+    };
+    let mut last: Vec<Option<bool>> = traces.iter().map(|t| t.value_at(start)).collect();
+    let _ = writeln!(out, "#{}", start * MS_PER_TICK);
+    out.push_str("$dumpvars\n");
+    for (i, value) in last.iter().enumerate() {
+        let _ = writeln!(out, "{}{}", value_char(*value), id_code(i));
+    }
+    out.push_str("$end\n");
+
+    for tick in start + 1..=end {
+        let mut changes = String::new();
+        for (i, trace) in traces.iter().enumerate() {
+            let value = trace.value_at(tick); // x until a signal was added
+            if value != last[i] {
+                let _ = writeln!(changes, "{}{}", value_char(value), id_code(i));
+                last[i] = value;
+            }
+        }
+        if !changes.is_empty() {
+            let _ = writeln!(out, "#{}", tick * MS_PER_TICK);
+            out.push_str(&changes);
+        }
+    }
+    // so the last tick gets a width in viewers
+    let _ = writeln!(out, "#{}", (end + 1) * MS_PER_TICK);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{HashSet, VecDeque};
+
+    #[test]
+    fn id_codes_are_short_and_unique() {
+        assert_eq!(id_code(0), "!");
+        assert_eq!(id_code(93), "~");
+        assert_eq!(id_code(94), "!!");
+        assert_eq!(id_code(94 + 94 * 94), "!!!");
+        let mut seen = HashSet::new();
+        for i in 0..20_000 {
+            let id = id_code(i);
+            assert!(id.bytes().all(|b| (b'!'..=b'~').contains(&b)), "{}", id);
+            assert!(seen.insert(id), "{} repeated", i);
+        }
+    }
+
+    fn trace(name: &str, tree: &str, start_tick: u64, values: &[bool]) -> Trace {
+        Trace {
+            name: name.to_string(),
+            tree: tree.to_string(),
+            index: 0,
+            start_tick,
+            values: VecDeque::from(values.to_vec()),
+        }
+    }
+
+    #[test]
+    fn writes_scopes_and_changes() {
+        let mut waveform = Waveform::new(100);
+        waveform.traces = vec![
+            trace("clock in", "", 0, &[false, true, false]),
+            trace("q", "cpu > alu", 1, &[true, true]),
+            trace("q", "cpu > alu", 0, &[false, false, false]),
+        ];
+        let vcd = write_vcd(&waveform, "main");
+        let expected = "$version SMLogic $end\n\
+            $comment 1 scrap mechanic tick = 25 ms $end\n\
+            $timescale 1 ms $end\n\
+            $scope module main $end\n\
+            $var wire 1 ! clock_in $end\n\
+            $scope module cpu $end\n\
+            $scope module alu $end\n\
+            $var wire 1 \" q $end\n\
+            $var wire 1 # q_2 $end\n\
+            $upscope $end\n\
+            $upscope $end\n\
+            $upscope $end\n\
+            $enddefinitions $end\n\
+            #0\n\
+            $dumpvars\n\
+            0!\n\
+            x\"\n\
+            0#\n\
+            $end\n\
+            #25\n\
+            1!\n\
+            1\"\n\
+            #50\n\
+            0!\n\
+            #75\n";
+        assert_eq!(vcd, expected);
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::VecDeque;
+
+pub const DEFAULT_HISTORY: usize = 10_000; // ticks kept per signal
+
+/// the recorded history of one signal
+#[derive(Clone)]
+pub struct Trace {
+    pub name: String,    // the parts label
+    pub tree: String,    // modules its nested in, like `ImportantGate.tree`, empty at top level
+    pub index: usize,    // simulation index
+    pub start_tick: u64, // tick of values[0]
+    pub values: VecDeque<bool>,
+}
+
+impl Trace {
+    pub fn full_name(&self) -> String {
+        if self.tree.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{} > {}", self.tree, self.name)
+        }
+    }
+
+    pub fn value_at(&self, tick: u64) -> Option<bool> {
+        let offset = tick.checked_sub(self.start_tick)?;
+        self.values.get(offset as usize).copied()
+    }
+
+    /// last tick that has a value
+    pub fn end_tick(&self) -> u64 {
+        (self.start_tick + self.values.len() as u64).saturating_sub(1)
+    }
+
+    /// (first tick, last tick, value) for every run of the same value that touches the range
+    pub fn runs(&self, from: u64, to: u64) -> Vec<(u64, u64, bool)> {
+        let mut runs: Vec<(u64, u64, bool)> = Vec::new();
+        let from = from.max(self.start_tick);
+        let to = to.min(self.end_tick());
+        if self.values.is_empty() || from > to {
+            return runs;
+        }
+        for tick in from..=to {
+            let value = self.values[(tick - self.start_tick) as usize];
+            match runs.last_mut() {
+                Some(run) if run.2 == value => run.1 = tick,
+                _ => runs.push((tick, tick, value)),
+            }
+        }
+        runs
+    }
+}
+
+/// ring buffers of the signals being recorded, filled once per tick by the simulation loop
+#[derive(Clone)]
+pub struct Waveform {
+    pub capacity: usize,
+    pub traces: Vec<Trace>,
+}
+
+impl Waveform {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            traces: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, outputs: &[bool]) {
+        for trace in &mut self.traces {
+            trace.values.push_back(outputs[trace.index]);
+            while trace.values.len() > self.capacity {
+                trace.values.pop_front();
+                trace.start_tick += 1;
+            }
+        }
+    }
+
+    pub fn contains(&self, index: usize) -> bool {
+        self.traces.iter().any(|t| t.index == index)
+    }
+
+    /// starts recording a signal, its history starts at the current tick
+    pub fn add_trace(&mut self, name: String, tree: String, index: usize, tick: u64, value: bool) {
+        if self.contains(index) {
+            return;
+        }
+        self.traces.push(Trace {
+            name,
+            tree,
+            index,
+            start_tick: tick,
+            values: VecDeque::from([value]),
+        });
+    }
+
+    pub fn remove_trace(&mut self, index: usize) {
+        self.traces.retain(|t| t.index != index);
+    }
+
+    /// throws away all history but keeps recording the same signals
+    pub fn clear_history(&mut self, tick: u64, outputs: &[bool]) {
+        for trace in &mut self.traces {
+            trace.start_tick = tick;
+            trace.values = VecDeque::from([outputs[trace.index]]);
+        }
+    }
+
+    /// forgets everything after a tick, for when the simulation is rewound
+    pub fn truncate(&mut self, tick: u64, outputs: &[bool]) {
+        for trace in &mut self.traces {
+            if trace.start_tick > tick {
+                trace.start_tick = tick;
+                trace.values.clear();
+            }
+            trace.values.truncate((tick - trace.start_tick) as usize);
+            trace.values.push_back(outputs[trace.index]);
+        }
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        for trace in &mut self.traces {
+            while trace.values.len() > self.capacity {
+                trace.values.pop_front();
+                trace.start_tick += 1;
+            }
+        }
+    }
+
+    /// first and last tick any trace has a value for
+    pub fn tick_range(&self) -> Option<(u64, u64)> {
+        let start = self.traces.iter().map(|t| t.start_tick).min()?;
+        let end = self.traces.iter().map(|t| t.end_tick()).max()?;
+        Some((start, end))
+    }
+}
+
+impl Default for Waveform {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorded(values: &[bool], capacity: usize) -> Waveform {
+        let mut waveform = Waveform::new(capacity);
+        waveform.add_trace("a".to_string(), String::new(), 0, 0, values[0]);
+        for value in &values[1..] {
+            waveform.record(&[*value]);
+        }
+        waveform
+    }
+
+    #[test]
+    fn keeps_the_last_capacity_ticks() {
+        let waveform = recorded(&[true, false, false, true, true], 3);
+        let trace = &waveform.traces[0];
+        assert_eq!(trace.start_tick, 2);
+        assert_eq!(trace.end_tick(), 4);
+        assert_eq!(trace.value_at(1), None);
+        assert_eq!(trace.value_at(2), Some(false));
+        assert_eq!(trace.value_at(4), Some(true));
+        assert_eq!(waveform.tick_range(), Some((2, 4)));
+    }
+
+    #[test]
+    fn splits_traces_into_runs() {
+        let waveform = recorded(&[true, true, false, true, true, true], 100);
+        let trace = &waveform.traces[0];
+        assert_eq!(
+            trace.runs(0, 5),
+            vec![(0, 1, true), (2, 2, false), (3, 5, true)]
+        );
+        assert_eq!(
+            trace.runs(1, 3),
+            vec![(1, 1, true), (2, 2, false), (3, 3, true)]
+        );
+        assert_eq!(trace.runs(7, 9), Vec::new());
+    }
+
+    #[test]
+    fn truncating_forgets_later_ticks() {
+        let mut waveform = recorded(&[true, false, true, false], 100);
+        waveform.truncate(2, &[false]);
+        let trace = &waveform.traces[0];
+        assert_eq!(trace.values, VecDeque::from([true, false, false]));
+        assert_eq!(trace.end_tick(), 2);
+    }
+}
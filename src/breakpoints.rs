@@ -0,0 +1,169 @@
+use crate::state::AppState;
+use egui::{Color32, Painter, Rect, Stroke, StrokeKind, Ui};
+use smlogic_core::breakpoints::{Breakpoint, bus_value};
+use std::collections::HashSet;
+
+const HIT_COLOR: Color32 = Color32::from_rgb(255, 60, 60);
+
+/// inputs for the breakpoints section of the simulator sidebar
+pub struct BreakpointInputs {
+    pub tick: u64,
+    pub bus: Vec<(String, usize)>, // (label, simulation index), most significant bit first
+    pub bus_name: String,
+    pub bus_value: u64,
+}
+
+impl BreakpointInputs {
+    pub const NEW: Self = Self {
+        tick: 0,
+        bus: Vec::new(),
+        bus_name: String::new(),
+        bus_value: 0,
+    };
+}
+
+impl AppState {
+    fn add_or_remove_breakpoint(&mut self, breakpoint: Breakpoint) {
+        if let Some(sim_state) = &self.sim_state {
+            let mut state = sim_state.lock();
+            if let Some(i) = state.breakpoints.iter().position(|b| *b == breakpoint) {
+                state.breakpoints.remove(i);
+                state.breakpoint_hit = None;
+            } else {
+                state.breakpoints.push(breakpoint);
+            }
+        }
+    }
+
+    /// adds a "pause when this turns on" breakpoint, or removes it if theres already one
+    pub fn toggle_breakpoint(&mut self, index: usize, name: String) {
+        self.add_or_remove_breakpoint(Breakpoint::TurnsOn { name, index });
+    }
+
+    pub fn toggle_bus_bit(&mut self, index: usize, name: String) {
+        let bus = &mut self.breakpoint_inputs.bus;
+        if let Some(i) = bus.iter().position(|(_, b)| *b == index) {
+            bus.remove(i);
+        } else {
+            bus.push((name, index));
+        }
+    }
+
+    pub fn draw_breakpoints(&mut self, ui: &mut Ui) {
+        let Some(sim_state) = self.sim_state.clone() else {
+            return;
+        };
+        ui.separator();
+        ui.label("Breakpoints:");
+        let (breakpoints, hit) = {
+            let state = sim_state.lock();
+            (state.breakpoints.clone(), state.breakpoint_hit)
+        };
+        let outputs = self.sim_state_outputs_snapshot.clone().unwrap_or_default();
+        let mut remove = None;
+        for (i, breakpoint) in breakpoints.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.small_button("x").clicked() {
+                    remove = Some(breakpoint.clone());
+                }
+                let mut text = breakpoint.to_label();
+                if let Breakpoint::BusEquals { bits, .. } = breakpoint
+                    && bits.iter().all(|b| *b < outputs.len())
+                {
+                    text = format!("{} (now {})", text, bus_value(bits, &outputs));
+                }
+                if hit == Some(i) {
+                    ui.colored_label(HIT_COLOR, text);
+                } else {
+                    ui.label(text);
+                }
+            });
+        }
+        if let Some(breakpoint) = remove {
+            self.add_or_remove_breakpoint(breakpoint);
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Pause at tick");
+            ui.add(egui::DragValue::new(&mut self.breakpoint_inputs.tick));
+            if ui.button("Add").clicked() {
+                let breakpoint = Breakpoint::Tick(self.breakpoint_inputs.tick);
+                sim_state.lock().breakpoints.push(breakpoint);
+            }
+        });
+
+        let inputs = &mut self.breakpoint_inputs;
+        if inputs.bus.is_empty() {
+            ui.small("use the \"Pick bus bits\" click mode to pause when a bus equals a value");
+            return;
+        }
+        let bits: Vec<&str> = inputs.bus.iter().map(|(name, _)| name.as_str()).collect();
+        ui.small(format!("bus (msb first): {}", bits.join(", ")));
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut inputs.bus_name)
+                    .hint_text("bus name")
+                    .desired_width(70.0),
+            );
+            ui.label("=");
+            let max = u64::MAX >> (64 - inputs.bus.len().min(64));
+            ui.add(egui::DragValue::new(&mut inputs.bus_value).range(0..=max));
+        });
+        ui.horizontal(|ui| {
+            if ui.button("Add").clicked() {
+                let name = if inputs.bus_name.is_empty() {
+                    "bus".to_string()
+                } else {
+                    inputs.bus_name.clone()
+                };
+                sim_state.lock().breakpoints.push(Breakpoint::BusEquals {
+                    name,
+                    bits: inputs.bus.iter().map(|(_, i)| *i).collect(),
+                    value: inputs.bus_value,
+                });
+            }
+            if ui.button("Clear bits").clicked() {
+                inputs.bus.clear();
+            }
+        });
+    }
+
+    /// outlines the parts that made the simulation pause
+    pub fn draw_breakpoint_highlight(&self, painter: &Painter) {
+        let Some(breakpoint) = &self.breakpoint_hit else {
+            return;
+        };
+        let indexes: HashSet<usize> = breakpoint.indexes().into_iter().collect();
+        // connections go by the part driving them, so module outputs highlight the module
+        let mut part_ids: HashSet<u64> = self
+            .canvas_snapshot
+            .connections
+            .iter()
+            .filter(|c| c.simulation_index.is_some_and(|i| indexes.contains(&i)))
+            .map(|c| c.start.part)
+            .collect();
+        part_ids.extend(
+            self.canvas_snapshot
+                .parts
+                .values()
+                .filter(|p| p.simulation_index.is_some_and(|i| indexes.contains(&i)))
+                .map(|p| p.id),
+        );
+        for part in part_ids
+            .iter()
+            .filter_map(|id| self.canvas_snapshot.parts.get(id))
+        {
+            let rect = Rect::from_min_max(
+                self.world_to_screen(part.pos),
+                self.world_to_screen(part.pos + part.part_data.size()),
+            )
+            .expand(4.0 * self.zoom);
+            painter.rect_stroke(
+                rect,
+                6.0 * self.zoom,
+                Stroke::new(3.0 * self.zoom, HIT_COLOR),
+                StrokeKind::Outside,
+            );
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use crate::parts::{PartData, sort_by_position};
+use crate::state::{AppState, Selection};
+use egui::Ui;
+use smlogic_core::bus::{Bus, BusFormat, MAX_BUS_WIDTH};
+
+/// inputs for the buses sections of the sidebar
+pub struct BusInputs {
+    pub name: String,
+    pub lsb_first: bool,
+    pub values: Vec<String>, // typed in values, by bus index
+}
+
+impl BusInputs {
+    pub const NEW: Self = Self {
+        name: String::new(),
+        lsb_first: false,
+        values: Vec::new(),
+    };
+
+    fn take_name(&mut self, default: usize) -> String {
+        if self.name.is_empty() {
+            format!("bus {}", default)
+        } else {
+            std::mem::take(&mut self.name)
+        }
+    }
+}
+
+impl AppState {
+    /// selected parts that can be bits of a bus, ordered like the connector does it
+    fn selected_bits(&self) -> Vec<u64> {
+        let mut parts: Vec<_> = self
+            .selection
+            .iter()
+            .filter_map(|s| match s {
+                Selection::Part(id) => self.canvas_snapshot.parts.get(id),
+                _ => None,
+            })
+            .filter(|p| {
+                matches!(
+                    p.part_data,
+                    PartData::Gate(_) | PartData::Timer(_) | PartData::IO(_)
+                )
+            })
+            .collect();
+        sort_by_position(&mut parts, |p| p.pos);
+        parts.iter().map(|p| p.id).collect()
+    }
+
+    fn add_bus(&mut self, mut bits: Vec<Vec<u64>>) {
+        if self.bus_inputs.lsb_first {
+            bits.reverse();
+        }
+        let name = self
+            .bus_inputs
+            .take_name(self.canvas_snapshot.buses.len() + 1);
+        self.canvas_snapshot.buses.push(Bus {
+            name,
+            bits,
+            format: BusFormat::Hex,
+        });
+    }
+
+    fn draw_new_bus_inputs(&mut self, ui: &mut Ui) -> bool {
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.bus_inputs.name)
+                    .hint_text("bus name")
+                    .desired_width(80.0),
+            );
+            ui.checkbox(&mut self.bus_inputs.lsb_first, "LSB first");
+        });
+        ui.button("Make bus").clicked()
+    }
+
+    /// lists the modules buses and makes new ones out of the selection, when not simulating
+    pub fn draw_bus_editor(&mut self, ui: &mut Ui) {
+        let bits = self.selected_bits();
+        if self.canvas_snapshot.buses.is_empty() && bits.len() < 2 {
+            return;
+        }
+        ui.separator();
+        ui.heading("Buses");
+        let mut remove = None;
+        for (i, bus) in self.canvas_snapshot.buses.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.small_button("x").clicked() {
+                    remove = Some(i);
+                }
+                ui.label(format!("{} ({} bits)", bus.name, bus.bits.len()));
+            });
+        }
+        if let Some(i) = remove {
+            self.push_undo();
+            self.canvas_snapshot.buses.remove(i);
+        }
+
+        if bits.len() < 2 {
+            return;
+        }
+        if bits.len() > MAX_BUS_WIDTH {
+            ui.small(format!("buses can only be {} bits wide", MAX_BUS_WIDTH));
+        } else if self.draw_new_bus_inputs(ui) {
+            self.push_undo();
+            self.add_bus(bits.into_iter().map(|id| vec![id]).collect());
+        }
+    }
+
+    /// the buses section of the simulator sidebar, shows values and lets them be typed in
+    pub fn draw_buses(&mut self, ui: &mut Ui) {
+        let Some(sim_state) = self.sim_state.clone() else {
+            return;
+        };
+        let picked: Vec<usize> = self.breakpoint_inputs.bus.iter().map(|(_, i)| *i).collect();
+        if self.canvas_snapshot.buses.is_empty() && picked.len() < 2 {
+            return;
+        }
+        ui.separator();
+        ui.label("Buses:");
+        let outputs = self.sim_state_outputs_snapshot.clone().unwrap_or_default();
+        let mut set = None;
+        let mut changed_format = false;
+        self.bus_inputs
+            .values
+            .resize(self.canvas_snapshot.buses.len(), String::new());
+        for (i, bus) in self.canvas_snapshot.buses.iter_mut().enumerate() {
+            let width = bus.bits.len();
+            let value = bus.value(&self.probe_indexes, &outputs);
+            ui.horizontal(|ui| {
+                ui.label(format!("{}:", bus.name));
+                match value {
+                    Some(value) => ui.monospace(bus.format.format(value, width)),
+                    None => ui.weak("missing parts"),
+                };
+            });
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_salt(("bus_format", i))
+                    .width(70.0)
+                    .selected_text(bus.format.to_label())
+                    .show_ui(ui, |ui| {
+                        for format in BusFormat::FORMATS {
+                            changed_format |= ui
+                                .selectable_value(&mut bus.format, *format, format.to_label())
+                                .changed();
+                        }
+                    });
+                let text = &mut self.bus_inputs.values[i];
+                let response = ui.add(egui::TextEdit::singleline(text).desired_width(60.0));
+                let entered =
+                    response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                if (ui.button("Set").clicked() || entered) && value.is_some() {
+                    match bus.format.parse(text, width) {
+                        Some(new_value) => {
+                            set = Some((bus.indexes(&self.probe_indexes), new_value))
+                        }
+                        None => {
+                            self.toasts.error(format!(
+                                "\"{}\" isnt a valid {} value for {}",
+                                text,
+                                bus.format.to_label(),
+                                bus.name
+                            ));
+                        }
+                    }
+                }
+            });
+        }
+        if changed_format {
+            self.has_unsaved_changes = true;
+        }
+        if let Some((Some(bits), value)) = set {
+            sim_state.lock().set_bus(&bits, value);
+        }
+
+        // bits picked with the "Pick bus bits" click mode can be saved as a bus too
+        if picked.len() >= 2 && picked.len() <= MAX_BUS_WIDTH && self.draw_new_bus_inputs(ui) {
+            let paths = {
+                let state = sim_state.lock();
+                picked
+                    .iter()
+                    .filter_map(|&i| state.part_paths.get(i).cloned())
+                    .collect()
+            };
+            self.has_unsaved_changes = true;
+            self.add_bus(paths);
+        }
+    }
+}
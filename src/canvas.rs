@@ -6,9 +6,11 @@ use crate::colors::{ColorPallet, POWERED_COLOR, UNPOWERED_COLOR};
 use crate::connections::draw_connection;
 use crate::exporter::get_bp_folder;
 use crate::lua_scripting::LuaScript;
+use crate::part_render::PartProperties;
 use crate::parts::{GATE_SIZE, PartType};
 use crate::state::{AppState, CanvasSnapshot, InteractionState, Selection, path_to_string};
 use crate::tools::{Tool, tool_label};
+use smlogic_core::testing::test_file_path;
 
 const BASE_KEYBINDS: &[&str] = &[
     "and",
@@ -27,6 +29,7 @@ const BASE_KEYBINDS: &[&str] = &[
     "exporter",
     "simulator pause",
     "simulator tick",
+    "simulator step back",
     "rename",
 ];
 
@@ -40,6 +43,7 @@ impl AppState {
         let (response, painter) =
             ui.allocate_painter(available, Sense::click_and_drag() | Sense::hover());
         let canvas_rect = response.rect;
+        self.canvas_rect = canvas_rect;
 
         painter.rect_filled(canvas_rect, 0.0, self.color_pallet.grid);
 
@@ -47,12 +51,14 @@ impl AppState {
             self.draw_grid(&painter, canvas_rect);
         }
         self.draw_parts(&painter);
+        self.draw_breakpoint_highlight(&painter);
         if !self.hide_connections {
             if self.draw_connections(&painter) {
                 for i in (0..self.canvas_snapshot.connections.len()).rev() {
                     let remove = {
                         let connection = &self.canvas_snapshot.connections[i];
-                        connection.start.pos(self).is_none() || connection.end.pos(self).is_none()
+                        connection.start.pos(&self.canvas_snapshot).is_none()
+                            || connection.end.pos(&self.canvas_snapshot).is_none()
                     };
                     if remove {
                         self.canvas_snapshot.connections.remove(i);
@@ -65,8 +71,8 @@ impl AppState {
             if let Some(Selection::Part(part_id)) = self.selection.get(0) {
                 for connection in &self.canvas_snapshot.connections {
                     if (&connection.start.part == part_id) | (&connection.end.part == part_id) {
-                        let start_pos = connection.start.pos(self);
-                        let end_pos = connection.end.pos(self);
+                        let start_pos = connection.start.pos(&self.canvas_snapshot);
+                        let end_pos = connection.end.pos(&self.canvas_snapshot);
                         if let (Some(start), Some(end)) = (start_pos, end_pos) {
                             draw_connection(self, start, end, &painter, true, false);
                         }
@@ -85,8 +91,8 @@ impl AppState {
             }
             if connector_data.previewing {
                 for connection in &connector_data.connection_preview {
-                    let start_pos = connection.start.pos(self);
-                    let end_pos = connection.end.pos(self);
+                    let start_pos = connection.start.pos(&self.canvas_snapshot);
+                    let end_pos = connection.end.pos(&self.canvas_snapshot);
                     if let (Some(start), Some(end)) = (start_pos, end_pos) {
                         draw_connection(self, start, end, &painter, true, false);
                     }
@@ -105,6 +111,7 @@ impl AppState {
             ctx.request_repaint();
         }
 
+        self.draw_module_breadcrumbs(ctx);
         self.toasts.show(ctx);
         (response, painter)
     }
@@ -290,6 +297,10 @@ impl AppState {
 
                 // tool settings
                 self.draw_sidebar_tool_properties(ui);
+                if self.active_tool.is_none() {
+                    self.draw_bus_editor(ui);
+                    self.draw_memory_editor(ui);
+                }
 
                 // properties
                 if self.selection.len() == 1 {
@@ -371,8 +382,7 @@ impl AppState {
                             if let Some(sim_state) = &self.sim_state {
                                 let mut state = sim_state.lock();
                                 let new_val = !state.part_outputs[gate.simulation_index];
-                                state.part_outputs[gate.simulation_index] = new_val;
-                                state.prev_outputs[gate.simulation_index] = new_val;
+                                state.set_output(gate.simulation_index, new_val);
                             }
                         }
                     }
@@ -430,6 +440,8 @@ impl AppState {
                                 connections: Vec::new(),
                                 parts: HashMap::new(),
                                 next_id: 0,
+                                buses: Vec::new(),
+                                memories: Vec::new(),
                             };
                             self.connection_counts.clear();
                             self.current_module_path = None;
@@ -499,13 +511,29 @@ impl AppState {
                             self.end_simulation();
                         }
                     }
+                    if ui
+                        .button("Open blueprint")
+                        .on_hover_text("Pull a blueprint from the game into the canvas")
+                        .clicked()
+                        && self.are_you_sure()
+                    {
+                        self.open_import_window();
+                    }
 
                     if let Some(path) = self.current_module_path.clone() {
                         ui.separator();
                         ui.label(format!(
                             "Editing: {}",
-                            path_to_string(path, self.project_folder.clone())
+                            path_to_string(path.clone(), self.project_folder.clone())
                         ));
+                        if test_file_path(&path).is_file()
+                            && ui
+                                .button("Run tests")
+                                .on_hover_text("Runs the .sml.test file next to this module")
+                                .clicked()
+                        {
+                            self.run_module_tests();
+                        }
                     }
                 });
                 ui.separator();
@@ -0,0 +1,553 @@
+use crate::exporter::{
+    BluePrint, ExportType, ExporterSettings, Placement, get_bp_folder, layout_source,
+    resolve_blueprint,
+};
+use crate::saveload::Config;
+use crate::state::CanvasSnapshot;
+use smlogic_core::compiled::Engine;
+use smlogic_core::error::ExportError;
+use smlogic_core::headless::{
+    DISPLAY_VECTORS, HeadlessSim, InputVector, benchmark, generated_display, parse_vectors,
+};
+use smlogic_core::savestate::SaveState;
+use smlogic_core::simulator::MIN_PARTS_PER_THREAD;
+use smlogic_core::testing::{parse_test_file, run_tests, test_file_path};
+use smlogic_core::vcd::write_vcd;
+use std::path::{Path, PathBuf};
+
+const USAGE: &str = "usage:
+  smlogic                      open the editor
+  smlogic export <module.sml> (--name <name> | --uuid <uuid> | --new <name>) [options]
+  smlogic import (--name <name> | --uuid <uuid>) <module.sml> [--bp-folder <dir>]
+  smlogic simulate <module.sml> [--vectors <file>] [--ticks <n>] [options]
+  smlogic test <module.sml>... [--project <dir>]
+  smlogic bench (<module.sml> | --display <w>x<h>) [--vectors <file>] [--ticks <n>] [options]
+
+export options:
+  --project <dir>        project folder module paths are relative to (default: the module's folder)
+  --bp-folder <dir>      scrap mechanic blueprints folder (default: the one from settings)
+  --rename <name>        rename an existing blueprint
+  --desc <text>          set the blueprint description
+  --icon <png>           set the blueprint icon (128x128 png)
+  --max-x/--max-y/--max-z <n>
+                         limit the size of the gate block
+  --keep-io-position     place IO and important parts as they are on the canvas
+  --io-x-scale/--io-y-scale <scale>
+                         canvas to SM scale used by --keep-io-position and --placement canvas
+  --keep-positions       put parts back where the last export of this module put them
+  --placement <order>    raster, modules or depth, what order gates fill the block in
+                         (default: raster), or canvas to put every part where it is on the
+                         canvas, scaled by --io-x-scale/--io-y-scale
+  --state <file>         start every part the way it is in a saved simulation (.smstate)
+
+import turns a blueprint back into a module, laid out left to right from its inputs
+
+simulate options:
+  --project <dir>        project folder module paths are relative to (default: the module's folder)
+  --vectors <file>       input vector file, one `tick: input label = 1/0` per line
+  --ticks <n>            how many ticks to run (default: the last tick in the vector file)
+  --changes              only print ticks where an output changed
+  --output <file>        write the csv to a file instead of printing it
+  --vcd <file>           also write every input, output and important gate to a .vcd file
+  --engine <engine>      simple or event (default: simple)
+  --threads <n>          split each tick of the simple engine over up to n threads (default: 1)
+
+test runs the <module>.sml.test file next to each module, exits with 1 if any test fails
+
+bench times every simulation engine on the same run (default: 10000 ticks) and checks they all
+give the same results, exits with 1 if they dont
+
+bench options:
+  --project <dir>        project folder module paths are relative to (default: the module's folder)
+  --threads <n>          also time the simple engine split over up to n threads (default: 1)
+  --history <n>          keep n ticks of rewind history while timing, like the editor (default: 0)
+  --display <w>x<h>      bench a generated display like the bad apple example instead of a module";
+
+/// handles command line arguments, returns none if the gui should be opened instead
+pub fn run(args: &[String]) -> Option<i32> {
+    let command = args.first()?;
+    let result = match command.as_str() {
+        "export" => export(&args[1..]),
+        "import" => import(&args[1..]),
+        "simulate" => simulate(&args[1..]),
+        "test" => test(&args[1..]),
+        "bench" => bench(&args[1..]),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            return Some(0);
+        }
+        _ => return None,
+    };
+    match result {
+        Ok(()) => Some(0),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            Some(1)
+        }
+    }
+}
+
+/// pulls the value out of a flag like `--max-x 20`
+fn flag_value<'a>(
+    args: &mut impl Iterator<Item = &'a String>,
+    flag: &str,
+) -> Result<String, String> {
+    args.next()
+        .cloned()
+        .ok_or_else(|| format!("{} needs a value", flag))
+}
+
+fn parse_value<T: std::str::FromStr>(value: String, flag: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {}: {}", flag, value))
+}
+
+fn parse_engine(value: String) -> Result<Engine, String> {
+    match value.as_str() {
+        "simple" => Ok(Engine::Simple),
+        "event" => Ok(Engine::EventDriven),
+        _ => Err(format!(
+            "invalid engine {}, should be simple or event",
+            value
+        )),
+    }
+}
+
+fn parse_placement(value: String) -> Result<Placement, String> {
+    match value.as_str() {
+        "raster" => Ok(Placement::Raster),
+        "modules" => Ok(Placement::Modules),
+        "depth" => Ok(Placement::Depth),
+        "canvas" => Ok(Placement::Canvas),
+        _ => Err(format!(
+            "invalid placement {}, should be raster, modules, depth or canvas",
+            value
+        )),
+    }
+}
+
+fn read_vectors(path: Option<PathBuf>) -> Result<Vec<InputVector>, String> {
+    let Some(path) = path else {
+        return Ok(Vec::new());
+    };
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| format!("failed to read {}: {}", path.to_string_lossy(), e))?;
+    parse_vectors(&contents).map_err(|e| format!("{}: {}", path.to_string_lossy(), e))
+}
+
+/// loads a module the same way the editor does, but fails if any sub module couldnt be loaded
+pub fn load_module(module: &Path, project: Option<PathBuf>) -> Result<CanvasSnapshot, String> {
+    let project = project.or_else(|| module.parent().map(|p| p.to_path_buf()));
+    let mut errors = Vec::new();
+    let canvas = CanvasSnapshot::load(
+        module.to_path_buf(),
+        project.clone(),
+        &mut errors,
+        Vec::new(),
+    )
+    .map_err(|e| format!("failed to load {}: {}", module.to_string_lossy(), e))?;
+
+    if !errors.is_empty() {
+        let mut messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        messages.sort();
+        messages.dedup();
+        return Err(format!(
+            "{} (is --project set to the right folder?)",
+            messages.join("\n")
+        ));
+    }
+    Ok(canvas)
+}
+
+fn export(args: &[String]) -> Result<(), String> {
+    let mut settings = ExporterSettings::NEW;
+    let mut module: Option<PathBuf> = None;
+    let mut project: Option<PathBuf> = None;
+    let mut bp_folder: Option<PathBuf> = None;
+    let mut target_set = false;
+    let mut rename: Option<String> = None;
+    let mut state_path: Option<PathBuf> = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--project" => project = Some(flag_value(&mut args, arg)?.into()),
+            "--bp-folder" => bp_folder = Some(flag_value(&mut args, arg)?.into()),
+            "--name" | "--uuid" | "--new" => {
+                if target_set {
+                    return Err("only one of --name, --uuid or --new can be used".to_string());
+                }
+                target_set = true;
+                let value = flag_value(&mut args, arg)?;
+                match arg.as_str() {
+                    "--name" => {
+                        settings.export_type = ExportType::FromName;
+                        settings.identifier = Some(value);
+                    }
+                    "--uuid" => {
+                        settings.export_type = ExportType::FromUUID;
+                        settings.identifier = Some(value);
+                    }
+                    _ => {
+                        settings.export_type = ExportType::New;
+                        settings.new_name = Some(value);
+                    }
+                }
+            }
+            "--rename" => rename = Some(flag_value(&mut args, arg)?),
+            "--desc" => settings.new_desc = Some(flag_value(&mut args, arg)?),
+            "--icon" => settings.new_icon = Some(flag_value(&mut args, arg)?.into()),
+            "--max-x" => settings.max_x = Some(parse_value(flag_value(&mut args, arg)?, arg)?),
+            "--max-y" => settings.max_y = Some(parse_value(flag_value(&mut args, arg)?, arg)?),
+            "--max-z" => settings.max_z = Some(parse_value(flag_value(&mut args, arg)?, arg)?),
+            "--keep-io-position" => settings.maintain_io_position = true,
+            "--io-x-scale" => settings.io_x_scale = parse_value(flag_value(&mut args, arg)?, arg)?,
+            "--io-y-scale" => settings.io_y_scale = parse_value(flag_value(&mut args, arg)?, arg)?,
+            "--keep-positions" => settings.keep_positions = true,
+            "--placement" => settings.placement = parse_placement(flag_value(&mut args, arg)?)?,
+            "--state" => state_path = Some(flag_value(&mut args, arg)?.into()),
+            _ if arg.starts_with("--") => {
+                return Err(format!("unknown option {}\n\n{}", arg, USAGE));
+            }
+            _ if module.is_none() => module = Some(arg.into()),
+            _ => return Err(format!("unexpected argument {}\n\n{}", arg, USAGE)),
+        }
+    }
+
+    let Some(module) = module else {
+        return Err(format!("no module supplied\n\n{}", USAGE));
+    };
+    if !target_set {
+        return Err(format!(
+            "pick a blueprint with --name, --uuid or --new\n\n{}",
+            USAGE
+        ));
+    }
+    if rename.is_some() {
+        if settings.export_type == ExportType::New {
+            return Err("--rename only works with --name or --uuid".to_string());
+        }
+        settings.new_name = rename;
+    }
+    let bp_folder = bp_folder
+        .or_else(|| Config::load().bp_folder)
+        .or_else(get_bp_folder)
+        .ok_or("no blueprint folder found, set one with --bp-folder")?;
+
+    let state = match state_path {
+        Some(path) => Some(
+            SaveState::load(&path)
+                .map_err(|e| format!("failed to read {}: {}", path.to_string_lossy(), e))?,
+        ),
+        None => None,
+    };
+
+    let source = layout_source(Some(&module));
+    let canvas = load_module(&module, project)?;
+    let mut blueprint = resolve_blueprint(&settings, bp_folder).map_err(|e| e.to_string())?;
+    let lost_timers = blueprint
+        .export_with_state(canvas, &settings, state.as_ref(), &source)
+        .map_err(|e| e.to_string())?;
+    println!(
+        "Exported blueprint \"{}\" ({})",
+        blueprint.name, blueprint.uuid
+    );
+    if lost_timers > 0 {
+        println!(
+            "{} timers had ticks in them, blueprints cant save those so they start empty",
+            lost_timers
+        );
+    }
+    Ok(())
+}
+
+fn import(args: &[String]) -> Result<(), String> {
+    let mut output: Option<PathBuf> = None;
+    let mut bp_folder: Option<PathBuf> = None;
+    let mut name: Option<String> = None;
+    let mut uuid: Option<String> = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bp-folder" => bp_folder = Some(flag_value(&mut args, arg)?.into()),
+            "--name" => name = Some(flag_value(&mut args, arg)?),
+            "--uuid" => uuid = Some(flag_value(&mut args, arg)?),
+            _ if arg.starts_with("--") => {
+                return Err(format!("unknown option {}\n\n{}", arg, USAGE));
+            }
+            _ if output.is_none() => output = Some(arg.into()),
+            _ => return Err(format!("unexpected argument {}\n\n{}", arg, USAGE)),
+        }
+    }
+
+    let Some(output) = output else {
+        return Err(format!("no module to write to supplied\n\n{}", USAGE));
+    };
+    let bp_folder = bp_folder
+        .or_else(|| Config::load().bp_folder)
+        .or_else(get_bp_folder)
+        .ok_or("no blueprint folder found, set one with --bp-folder")?;
+    let blueprint = match (name, uuid) {
+        (Some(name), None) => BluePrint::from_name(&name, &bp_folder),
+        (None, Some(uuid)) => BluePrint::from_uuid(uuid, bp_folder),
+        _ => {
+            return Err(format!(
+                "pick a blueprint with --name or --uuid\n\n{}",
+                USAGE
+            ));
+        }
+    }
+    .ok_or_else(|| ExportError::BlueprintNotFound.to_string())?;
+
+    let canvas = blueprint.import().map_err(|e| e.to_string())?;
+    canvas
+        .save(output.clone())
+        .map_err(|e| format!("failed to write {}: {}", output.to_string_lossy(), e))?;
+    println!(
+        "Imported \"{}\" into {} ({} parts)",
+        blueprint.name,
+        output.to_string_lossy(),
+        canvas.parts.len()
+    );
+    Ok(())
+}
+
+fn simulate(args: &[String]) -> Result<(), String> {
+    let mut module: Option<PathBuf> = None;
+    let mut project: Option<PathBuf> = None;
+    let mut vectors_path: Option<PathBuf> = None;
+    let mut ticks: Option<u64> = None;
+    let mut changes_only = false;
+    let mut output: Option<PathBuf> = None;
+    let mut vcd: Option<PathBuf> = None;
+    let mut engine = Engine::Simple;
+    let mut threads: usize = 1;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--project" => project = Some(flag_value(&mut args, arg)?.into()),
+            "--vectors" => vectors_path = Some(flag_value(&mut args, arg)?.into()),
+            "--ticks" => ticks = Some(parse_value(flag_value(&mut args, arg)?, arg)?),
+            "--changes" => changes_only = true,
+            "--output" => output = Some(flag_value(&mut args, arg)?.into()),
+            "--vcd" => vcd = Some(flag_value(&mut args, arg)?.into()),
+            "--engine" => engine = parse_engine(flag_value(&mut args, arg)?)?,
+            "--threads" => threads = parse_value(flag_value(&mut args, arg)?, arg)?,
+            _ if arg.starts_with("--") => {
+                return Err(format!("unknown option {}\n\n{}", arg, USAGE));
+            }
+            _ if module.is_none() => module = Some(arg.into()),
+            _ => return Err(format!("unexpected argument {}\n\n{}", arg, USAGE)),
+        }
+    }
+
+    let Some(module) = module else {
+        return Err(format!("no module supplied\n\n{}", USAGE));
+    };
+    let vectors = read_vectors(vectors_path)?;
+    let Some(ticks) = ticks.or_else(|| vectors.last().map(|v| v.tick)) else {
+        return Err("nothing to run, pass --ticks or a --vectors file".to_string());
+    };
+
+    let canvas = load_module(&module, project)?;
+    let mut sim = HeadlessSim::new(&canvas);
+    sim.state.engine = engine;
+    sim.state.threads = threads.max(1);
+    if vcd.is_some() {
+        sim.state.waveform.set_capacity(ticks as usize + 1); // keep the whole run
+        sim.record_all();
+    }
+    let rows = sim.run(&vectors, ticks).map_err(|e| e.to_string())?;
+
+    if let Some(path) = vcd {
+        let top = module.file_stem().unwrap_or_default().to_string_lossy();
+        std::fs::write(&path, write_vcd(&sim.state.waveform, &top))
+            .map_err(|e| format!("failed to write {}: {}", path.to_string_lossy(), e))?;
+    }
+
+    let mut csv = String::from("tick");
+    for (label, _) in &sim.outputs {
+        csv.push(',');
+        csv.push_str(label);
+    }
+    csv.push('\n');
+    let mut last: Option<&Vec<bool>> = None;
+    for (tick, values) in &rows {
+        if changes_only && last == Some(values) {
+            continue;
+        }
+        last = Some(values);
+        csv.push_str(&tick.to_string());
+        for value in values {
+            csv.push_str(if *value { ",1" } else { ",0" });
+        }
+        csv.push('\n');
+    }
+
+    match output {
+        Some(path) => std::fs::write(&path, csv)
+            .map_err(|e| format!("failed to write {}: {}", path.to_string_lossy(), e))?,
+        None => print!("{}", csv),
+    }
+    Ok(())
+}
+
+fn test(args: &[String]) -> Result<(), String> {
+    let mut modules: Vec<PathBuf> = Vec::new();
+    let mut project: Option<PathBuf> = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--project" => project = Some(flag_value(&mut args, arg)?.into()),
+            _ if arg.starts_with("--") => {
+                return Err(format!("unknown option {}\n\n{}", arg, USAGE));
+            }
+            _ => modules.push(arg.into()),
+        }
+    }
+    if modules.is_empty() {
+        return Err(format!("no module supplied\n\n{}", USAGE));
+    }
+
+    let mut total = 0;
+    let mut failed = 0;
+    for module in &modules {
+        let test_path = test_file_path(module);
+        let contents = std::fs::read_to_string(&test_path)
+            .map_err(|e| format!("failed to read {}: {}", test_path.to_string_lossy(), e))?;
+        let cases = parse_test_file(&contents)
+            .map_err(|e| format!("{}: {}", test_path.to_string_lossy(), e))?;
+        let canvas = load_module(module, project.clone())?;
+
+        println!("{}", module.to_string_lossy());
+        for result in run_tests(&canvas, &cases) {
+            total += 1;
+            let name = if result.name.is_empty() {
+                "(unnamed)"
+            } else {
+                &result.name
+            };
+            if result.passed() {
+                println!("  passed: {}", name);
+                continue;
+            }
+            failed += 1;
+            println!("  FAILED: {}", name);
+            if let Some(error) = &result.error {
+                println!("    {}", error);
+            }
+            for failure in &result.failures {
+                println!("    {}", failure);
+            }
+        }
+    }
+
+    if failed > 0 {
+        Err(format!("{} of {} tests failed", failed, total))
+    } else {
+        println!("all {} tests passed", total);
+        Ok(())
+    }
+}
+
+fn bench(args: &[String]) -> Result<(), String> {
+    let mut module: Option<PathBuf> = None;
+    let mut project: Option<PathBuf> = None;
+    let mut vectors_path: Option<PathBuf> = None;
+    let mut ticks: u64 = 10_000;
+    let mut threads: usize = 1;
+    let mut history: usize = 0;
+    let mut display: Option<(usize, usize)> = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--project" => project = Some(flag_value(&mut args, arg)?.into()),
+            "--vectors" => vectors_path = Some(flag_value(&mut args, arg)?.into()),
+            "--ticks" => ticks = parse_value(flag_value(&mut args, arg)?, arg)?,
+            "--threads" => threads = parse_value(flag_value(&mut args, arg)?, arg)?,
+            "--history" => history = parse_value(flag_value(&mut args, arg)?, arg)?,
+            "--display" => {
+                let value = flag_value(&mut args, arg)?;
+                let Some((width, height)) = value.split_once('x') else {
+                    return Err(format!("{} should look like 48x36, got {}", arg, value));
+                };
+                display = Some((
+                    parse_value(width.into(), arg)?,
+                    parse_value(height.into(), arg)?,
+                ));
+            }
+            _ if arg.starts_with("--") => {
+                return Err(format!("unknown option {}\n\n{}", arg, USAGE));
+            }
+            _ if module.is_none() => module = Some(arg.into()),
+            _ => return Err(format!("unexpected argument {}\n\n{}", arg, USAGE)),
+        }
+    }
+    let (canvas, vectors) = match (module, display) {
+        (Some(module), None) => (load_module(&module, project)?, read_vectors(vectors_path)?),
+        (None, Some((width, height))) => {
+            let vectors = match vectors_path {
+                Some(_) => read_vectors(vectors_path)?,
+                None => parse_vectors(DISPLAY_VECTORS).map_err(|e| e.to_string())?,
+            };
+            let canvas = generated_display(width, height);
+            println!(
+                "generated a {}x{} display, {} parts",
+                width,
+                height,
+                canvas.parts.len()
+            );
+            (canvas, vectors)
+        }
+        (Some(_), Some(_)) => {
+            return Err(format!("--display replaces the module\n\n{}", USAGE));
+        }
+        (None, None) => return Err(format!("no module supplied\n\n{}", USAGE)),
+    };
+
+    let results =
+        benchmark(&canvas, &vectors, ticks, threads, history).map_err(|e| e.to_string())?;
+    let baseline = results.first().map(|r| r.ticks_per_second).unwrap_or(1.0);
+    println!(
+        "{:<14}{:>14}{:>10}  results",
+        "engine", "ticks/sec", "speedup"
+    );
+    let mut mismatches = 0;
+    for result in &results {
+        let check = match result.first_mismatch {
+            Some(tick) => {
+                mismatches += 1;
+                format!("DIFFERENT from tick {}", tick)
+            }
+            None => "identical".to_string(),
+        };
+        let name = if result.threads > 1 {
+            format!("{} x{}", result.engine.to_label(), result.threads)
+        } else {
+            result.engine.to_label().to_string()
+        };
+        println!(
+            "{:<14}{:>14.0}{:>9.2}x  {}",
+            name,
+            result.ticks_per_second,
+            result.ticks_per_second / baseline,
+            check
+        );
+    }
+    let used = results.iter().map(|r| r.threads).max().unwrap_or(1);
+    if threads > used {
+        println!(
+            "only {} of the {} threads would be used, each thread needs at least {} parts",
+            used, threads, MIN_PARTS_PER_THREAD
+        );
+    }
+    if mismatches > 0 {
+        return Err(format!("{} engines gave different results", mismatches));
+    }
+    Ok(())
+}
@@ -136,7 +136,7 @@ impl Default for ColorPallet {
     }
 }
 
-pub const DEFAULT_GATE_COLOR: Color32 = Color32::from_rgb(0xDF, 0x7F, 0x01);
+pub use smlogic_core::parts::DEFAULT_GATE_COLOR;
 
 /// paint tool palette; 10 base colors × 4 shades.
 pub const SM_PALETTE: [[Color32; 4]; 10] = [
@@ -1,19 +1,12 @@
 use crate::colors::POWERED_COLOR;
-use crate::parts::{PartData, Port};
+use crate::parts::PartData;
 use crate::state::{AppState, Selection};
 use eframe::epaint::PathShape;
 use egui::{Color32, Painter, Pos2, Stroke};
-use serde::{Deserialize, Serialize};
 
-pub const WIRE_WIDTH: f32 = 2.0;
+pub use smlogic_core::connections::Connection;
 
-#[derive(Clone, Deserialize, Serialize, PartialEq)]
-pub struct Connection {
-    pub start: Port,
-    pub end: Port,
-    #[serde(skip)]
-    pub simulation_index: Option<usize>,
-}
+pub const WIRE_WIDTH: f32 = 2.0;
 
 pub fn dist_point_to_segment(p: Pos2, a: Pos2, b: Pos2) -> f32 {
     let ab = b - a;
@@ -131,8 +124,8 @@ impl AppState {
             if powered {
                 continue;
             }
-            let start_pos = connection.start.pos(self);
-            let end_pos = connection.end.pos(self);
+            let start_pos = connection.start.pos(&self.canvas_snapshot);
+            let end_pos = connection.end.pos(&self.canvas_snapshot);
             if let (Some(start), Some(end)) = (start_pos, end_pos) {
                 draw_connection(
                     self,
@@ -154,8 +147,8 @@ impl AppState {
             if !powered {
                 continue;
             }
-            let start_pos = connection.start.pos(self);
-            let end_pos = connection.end.pos(self);
+            let start_pos = connection.start.pos(&self.canvas_snapshot);
+            let end_pos = connection.end.pos(&self.canvas_snapshot);
             if let (Some(start), Some(end)) = (start_pos, end_pos) {
                 draw_connection(
                     self,
@@ -1,556 +1,393 @@
-use crate::{
-    simulator::{PartType, get_canvas_raw_data},
-    state::{AppState, CanvasSnapshot},
-};
-
-use egui::Pos2;
-use egui_notify::Toasts;
-use serde::{Deserialize, Serialize};
-use serde_json::{Value, json};
-use std::fs;
-use std::path::{Path, PathBuf};
-use uuid::Uuid;
-
-const DEFAULT_ICON: &[u8] = include_bytes!("../assets/default_icon.png");
-
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub enum ExportType {
-    FromName,
-    FromUUID,
-    New,
+use crate::state::{AppState, CanvasSnapshot};
+use crate::tools::Tool;
+use egui::{Color32, Pos2, Sense, Ui, pos2, vec2};
+use smlogic_core::error::ExportError;
+use smlogic_core::savestate::SaveState;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+pub use smlogic_core::exporter::*;
+
+const PREVIEW_SIZE: f32 = 320.0;
+const KEPT_WARNING: &str = "are where the last export put them, Placement and the size limits \
+only apply to new parts. Turn off Keep positions to lay everything out again";
+
+#[derive(Clone, PartialEq)]
+pub enum PreviewView {
+    Isometric,
+    Layers, // one z layer at a time, looking down
 }
-impl ExportType {
-    pub const TYPES: &[Self] = &[Self::FromName, Self::FromUUID, Self::New];
+
+impl PreviewView {
+    pub const VIEWS: &[Self] = &[Self::Isometric, Self::Layers];
 
     pub fn to_label(&self) -> &'static str {
         match self {
-            Self::FromName => "Overwrite by name",
-            Self::FromUUID => "Overwrite by UUID",
-            Self::New => "New blueprint",
+            Self::Isometric => "3D",
+            Self::Layers => "Layers",
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub struct ExporterSettings {
-    pub maintain_io_position: bool,
-    pub io_x_scale: f32, // if maintiain io position is true, scales down canvas positions to SM ones
-    pub io_y_scale: f32,
-    pub max_x: Option<usize>,
-    pub max_y: Option<usize>,
-    pub max_z: Option<usize>,
-    pub export_type: ExportType,
-    pub identifier: Option<String>,
-    pub new_name: Option<String>,
-    pub new_desc: Option<String>,
-    pub new_icon: Option<std::path::PathBuf>,
+/// the blueprint folder, export type and identifier a blueprint path was found from
+type BlueprintKey = (Option<PathBuf>, ExportType, Option<String>);
+
+/// inputs for the export preview window
+pub struct ExportPreviewInputs {
+    pub open: bool,
+    pub preview: ExportPreview,
+    pub view: PreviewView,
+    pub layer: i32,
+    // everything below is cached so dragging a setting doesnt redo all of it every frame
+    data: Option<(CanvasSnapshot, PreviewData)>, // the canvas it was flattened from
+    blueprint: Option<(BlueprintKey, Option<PathBuf>)>,
+    draw_list: Vec<(BlockPos, Color32, [bool; 3])>, // back to front, which faces can be seen
 }
 
-impl ExporterSettings {
+impl ExportPreviewInputs {
     pub const NEW: Self = Self {
-        maintain_io_position: false,
-        io_x_scale: 1.0 / 80.0,
-        io_y_scale: 1.0 / 60.0,
-        max_x: None,
-        max_y: None,
-        max_z: None,
-        export_type: ExportType::FromName,
-        identifier: None,
-        new_name: None,
-        new_desc: None,
-        new_icon: None,
+        open: false,
+        preview: ExportPreview {
+            blocks: Vec::new(),
+            kept: 0,
+        },
+        view: PreviewView::Isometric,
+        layer: 0,
+        data: None,
+        blueprint: None,
+        draw_list: Vec::new(),
     };
 }
 
-impl Default for ExporterSettings {
-    fn default() -> Self {
-        Self::NEW
+// isometric projection, x goes down right, y down left and z up
+fn project(x: i32, y: i32, z: i32) -> Pos2 {
+    pos2((x - y) as f32 * 0.866, (x + y) as f32 * 0.5 - z as f32)
+}
+
+/// where the blueprint the settings point at is, only needed to find where parts went last
+/// export. new blueprints dont have one yet
+fn preview_blueprint_path(key: &BlueprintKey) -> Option<PathBuf> {
+    let (Some(bp_folder), export_type, Some(identifier)) = key else {
+        return None;
+    };
+    match export_type {
+        ExportType::FromName => BluePrint::from_name(identifier, bp_folder).map(|bp| bp.path),
+        ExportType::FromUUID => Some(bp_folder.join(identifier)),
+        ExportType::New => None,
     }
 }
 
+/// the blocks sorted back to front, with which of the top, +x and +y faces arent hidden behind
+/// a solid block
+fn isometric_draw_list(preview: &ExportPreview) -> Vec<(BlockPos, Color32, [bool; 3])> {
+    let solid: HashSet<BlockPos> = preview
+        .blocks
+        .iter()
+        .filter(|b| b.kind != BlockKind::Glass)
+        .map(|b| b.cell)
+        .collect();
+    let mut blocks: Vec<&PreviewBlock> = preview.blocks.iter().collect();
+    blocks.sort_by_key(|b| b.cell.0 + b.cell.1 + b.cell.2);
+    blocks
+        .into_iter()
+        .map(|block| {
+            let (x, y, z) = block.cell;
+            let color = match block.kind {
+                BlockKind::Glass => block.color.gamma_multiply(0.25),
+                _ => block.color,
+            };
+            let visible = [(x, y, z + 1), (x + 1, y, z), (x, y + 1, z)]
+                .map(|neighbour| !solid.contains(&neighbour));
+            (block.cell, color, visible)
+        })
+        .collect()
+}
+
 impl AppState {
     pub fn export(&mut self, exporter_settings: ExporterSettings) {
-        if (exporter_settings.max_x.unwrap_or(1) <= 0)
-            | (exporter_settings.max_y.unwrap_or(1) <= 0)
-            | (exporter_settings.max_z.unwrap_or(1) <= 0)
-        {
-            self.toasts.error("cant set max size <= 0!");
+        self.export_state(exporter_settings, None);
+    }
+
+    /// exports with the last used exporter settings, with every part starting the way it is in
+    /// the simulation right now
+    pub fn export_current_state(&mut self) {
+        let Some(sim_state) = &self.sim_state else {
             return;
-        }
-        if let Some(bp_folder) = self.bp_folder.clone() {
-            let mut blueprint: Option<BluePrint> = match exporter_settings.export_type {
-                ExportType::FromName => {
-                    if let Some(ref name) = exporter_settings.identifier {
-                        if let Some(bp) = BluePrint::from_name(&name, &bp_folder) {
-                            Some(bp)
-                        } else {
-                            self.toasts.error("couldn't find blueprint!");
-                            None
-                        }
-                    } else {
-                        self.toasts.error("no name supplied?");
-                        None
-                    }
-                }
-                ExportType::FromUUID => {
-                    if let Some(ref uuid) = exporter_settings.identifier {
-                        if let Some(bp) = BluePrint::from_uuid(uuid.clone(), bp_folder) {
-                            Some(bp)
-                        } else {
-                            self.toasts.error("couldn't find blueprint!");
-                            None
-                        }
-                    } else {
-                        self.toasts.error("no uuid supplied?");
-                        None
-                    }
-                }
-                ExportType::New => {
-                    if let Some(ref name) = exporter_settings.new_name {
-                        if name.is_empty() {
-                            self.toasts.error("new blueprints need a name!");
-                            None
-                        } else {
-                            let icon = if let Some(ref icon_path) = exporter_settings.new_icon {
-                                if let Ok(icon_data) = fs::read(icon_path) {
-                                    if !is_128x128_png(&icon_data) {
-                                        self.toasts.error("icon must be a 128x128 png!");
-                                        return;
-                                    }
-                                    Some(icon_data)
-                                } else {
-                                    self.toasts.error("failed to read supplied icon!");
-                                    return;
-                                }
-                            } else {
-                                None
-                            };
-                            Some(BluePrint::new(
-                                name.clone(),
-                                exporter_settings.new_desc.clone().unwrap_or(String::new()),
-                                bp_folder,
-                                icon,
-                            ))
-                        }
-                    } else {
-                        self.toasts.error("new blueprints need a name!");
-                        None
-                    }
-                }
-            };
+        };
+        let save = SaveState::capture(&sim_state.lock());
+        self.export_state(self.config.export_settings.clone(), Some(&save));
+    }
 
-            if let Some(ref mut bp) = blueprint {
-                if bp.description.is_empty() {
-                    bp.description = "#{STEAM_WORKSHOP_NO_DESCRIPTION}".to_string();
-                }
-                if matches!(
-                    exporter_settings.export_type,
-                    ExportType::FromName | ExportType::FromUUID
-                ) {
-                    if let Some(ref name) = exporter_settings.new_name {
-                        bp.name = name.clone()
-                    }
-                    if let Some(ref desc) = exporter_settings.new_desc {
-                        bp.description = desc.clone()
-                    }
-                    if let Some(ref icon_path) = exporter_settings.new_icon {
-                        if let Ok(icon_data) = fs::read(icon_path) {
-                            if !is_128x128_png(&icon_data) {
-                                self.toasts.error("icon must be a 128x128 png!");
-                                return;
-                            }
-                            bp.icon = Some(icon_data);
-                        } else {
-                            self.toasts.error("failed to read supplied icon!");
-                            return;
-                        }
-                    }
-                }
-                bp.export(
-                    self.canvas_snapshot.clone(),
-                    &mut self.toasts,
-                    &exporter_settings,
-                );
-            } else {
-                return;
-            }
-        } else {
+    fn export_state(&mut self, exporter_settings: ExporterSettings, state: Option<&SaveState>) {
+        let Some(bp_folder) = self.bp_folder.clone() else {
             self.toasts
-                .error("No Blueprint folder set! (check settings)");
+                .error(ExportError::NoBlueprintFolder.to_string());
             return;
+        };
+        let source = layout_source(self.current_module_path.as_deref());
+        let result = resolve_blueprint(&exporter_settings, bp_folder).and_then(|mut bp| {
+            bp.export_with_state(
+                self.canvas_snapshot.clone(),
+                &exporter_settings,
+                state,
+                &source,
+            )
+        });
+        match result {
+            Ok(lost_timers) => {
+                self.toasts.success("Exported blueprint!");
+                if lost_timers > 0 {
+                    self.toasts.warning(format!(
+                        "{} timers had ticks in them, blueprints cant save those so they start \
+                        empty",
+                        lost_timers
+                    ));
+                }
+            }
+            Err(e) => {
+                self.toasts.error(e.to_string());
+            }
         }
     }
-}
-
-// this function was ai generated, didnt feel like doing all that math myself
-fn compute_positions(
-    total: usize,
-    io_indices: &[usize],
-    canvas_positions: &[Pos2],
-    settings: &ExporterSettings,
-) -> Vec<(i32, i32, i32)> {
-    let io_set: std::collections::HashSet<usize> = io_indices.iter().copied().collect();
-    let non_io_count = total - io_indices.len();
-    let io_count = io_indices.len();
 
-    let n = non_io_count as f32;
-    let ceil_div = |a: f32, b: f32| (a / b).ceil() as usize;
-    let (eff_x, eff_y, eff_z) = match (settings.max_x, settings.max_y, settings.max_z) {
-        (Some(x), Some(y), Some(z)) => (x.max(1), y.max(1), z.max(1)),
-        (Some(x), Some(y), None) => (x.max(1), y.max(1), ceil_div(n, (x * y) as f32).max(1)),
-        (Some(x), None, Some(z)) => (x.max(1), ceil_div(n, (x * z) as f32).max(1), z.max(1)),
-        (None, Some(y), Some(z)) => (ceil_div(n, (y * z) as f32).max(1), y.max(1), z.max(1)),
-        (Some(x), None, None) => {
-            let s = (n / x as f32).ceil().sqrt().ceil() as usize;
-            (x.max(1), s.max(1), s.max(1))
+    /// settings the preview should use, the exporter tools if its open
+    fn preview_settings(&self) -> ExporterSettings {
+        match &self.active_tool {
+            Some(Tool::Exporter(settings)) => settings.clone(),
+            _ => self.config.export_settings.clone(),
         }
-        (None, Some(y), None) => {
-            let s = (n / y as f32).ceil().sqrt().ceil() as usize;
-            (s.max(1), y.max(1), s.max(1))
-        }
-        (None, None, Some(z)) => {
-            let s = (n / z as f32).ceil().sqrt().ceil() as usize;
-            (s.max(1), s.max(1), z.max(1))
+    }
+
+    /// lays the preview out again, only flattening the canvas or looking for the blueprint
+    /// again if they changed since last time
+    pub fn refresh_export_preview(&mut self) {
+        let settings = self.preview_settings();
+        let cache = &mut self.export_preview;
+        if cache
+            .data
+            .as_ref()
+            .is_none_or(|(canvas, _)| *canvas != self.canvas_snapshot)
+        {
+            let data = PreviewData::new(self.canvas_snapshot.clone());
+            cache.data = Some((self.canvas_snapshot.clone(), data));
         }
-        (None, None, None) => {
-            let s = n.cbrt().ceil() as usize;
-            (s.max(1), s.max(1), s.max(1))
+        let key: BlueprintKey = (
+            self.bp_folder.clone(),
+            settings.export_type.clone(),
+            settings.identifier.clone(),
+        );
+        if cache
+            .blueprint
+            .as_ref()
+            .is_none_or(|(cached, _)| *cached != key)
+        {
+            let path = preview_blueprint_path(&key);
+            cache.blueprint = Some((key, path));
         }
-    };
-
-    let io_side = (io_count as f32).sqrt().ceil() as usize;
-
-    let mut out = vec![(0i32, 0i32, 0i32); total];
-    let mut gate_counter = 0;
-    let mut io_counter = 0;
-
-    for i in 0..total {
-        out[i] = if io_set.contains(&i) {
-            let pos = if settings.maintain_io_position {
-                (
-                    -1,
-                    -(canvas_positions[i].x * settings.io_x_scale).round() as i32,
-                    -(canvas_positions[i].y * settings.io_y_scale).round() as i32,
-                )
-            } else {
-                let side = io_side.max(1);
-                (
-                    -1,
-                    -((io_counter % side) as i32),
-                    -((io_counter / side) as i32),
-                )
-            };
-            io_counter += 1;
-            pos
-        } else {
-            let x = (gate_counter % eff_x) as i32;
-            let y = ((gate_counter / eff_x) % eff_y) as i32;
-            let z = ((gate_counter / (eff_x * eff_y)) % eff_z) as i32;
-            gate_counter += 1;
-            (x, y, z)
+        let Some((_, data)) = &cache.data else {
+            return;
         };
-    }
-
-    // normalize IO parts so the corner lines up with y=1, z=0
-    if let (Some(&min_y), Some(&min_z)) = (
-        io_indices
-            .iter()
-            .map(|&i| out[i].1)
-            .collect::<Vec<_>>()
-            .iter()
-            .min(),
-        io_indices
-            .iter()
-            .map(|&i| out[i].2)
-            .collect::<Vec<_>>()
-            .iter()
-            .min(),
-    ) {
-        for &i in io_indices {
-            out[i].1 = out[i].1 - min_y + 1;
-            out[i].2 = out[i].2 - min_z;
+        let blueprint_path = cache
+            .blueprint
+            .as_ref()
+            .and_then(|(_, path)| path.as_deref());
+        cache.preview = preview_export(
+            data,
+            &settings,
+            blueprint_path,
+            &layout_source(self.current_module_path.as_deref()),
+        );
+        cache.draw_list = isometric_draw_list(&cache.preview);
+        if let Some((min, max)) = cache.preview.bounds() {
+            cache.layer = cache.layer.clamp(min.2, max.2);
         }
     }
 
-    out
-}
-
-fn get_user_folder() -> Option<PathBuf> {
-    #[cfg(target_os = "windows")]
-    let base = PathBuf::from(std::env::var("APPDATA").ok()?);
-
-    #[cfg(not(target_os = "windows"))]
-    let base = dirs::home_dir()?.join(
-        ".steam/steam/steamapps/compatdata/387990/pfx/drive_c/users/steamuser/Application Data",
-    ); // untested :P
-
-    let folder = base.join("Axolot Games/Scrap Mechanic/User/");
-    folder.is_dir().then_some(folder)
-}
-
-pub fn get_bp_folder() -> Option<PathBuf> {
-    let user_folder = get_user_folder()?;
-    let users: Vec<_> = fs::read_dir(&user_folder)
-        .ok()?
-        .filter_map(|e| e.ok())
-        .filter(|e| e.path().is_dir())
-        .collect();
-
-    if users.len() == 1 {
-        Some(users[0].path().join("Blueprints/"))
-    } else {
-        None // more than one user, cant auto-detect (not sure why there would be multiple anyway??)
+    pub fn open_export_preview(&mut self) {
+        self.refresh_export_preview();
+        self.export_preview.open = true;
     }
-}
 
-fn get_id_from_name(name: &str, folder: &Path) -> Option<(String, PathBuf, Value)> {
-    for entry in fs::read_dir(folder).ok()? {
-        let Ok(entry) = entry else {
-            continue;
+    fn draw_isometric_preview(&self, ui: &mut Ui) {
+        let preview = &self.export_preview.preview;
+        let (response, painter) =
+            ui.allocate_painter(vec2(ui.available_width(), PREVIEW_SIZE), Sense::hover());
+        let rect = response.rect;
+        painter.rect_filled(rect, 4.0, self.color_pallet.grid);
+        let Some((min, max)) = preview.bounds() else {
+            return;
         };
-        let path = entry.path();
-        if !path.is_dir() {
-            continue;
-        }
-
-        if let Ok(contents) = fs::read_to_string(path.join("description.json")) {
-            if let Ok(json) = serde_json::from_str::<Value>(&contents) {
-                if json["name"].as_str() == Some(name) {
-                    return Some((entry.file_name().to_string_lossy().into_owned(), path, json));
+        // fit the whole thing in, corners of the bounding box are the furthest out it goes
+        let corners = [
+            project(min.0, max.1 + 1, min.2),
+            project(max.0 + 1, min.1, min.2),
+            project(min.0, min.1, max.2 + 1),
+            project(max.0 + 1, max.1 + 1, min.2),
+        ];
+        let projected = egui::Rect::from_points(&corners);
+        let scale = ((rect.width() - 16.0) / projected.width())
+            .min((rect.height() - 16.0) / projected.height());
+        let offset = rect.center() - projected.center().to_vec2() * scale;
+        let to_screen = |x: i32, y: i32, z: i32| offset + project(x, y, z).to_vec2() * scale;
+
+        for &((x, y, z), color, visible) in &self.export_preview.draw_list {
+            // only the faces facing the camera, and only if nothing solid is in front of them
+            let faces = [
+                (
+                    [
+                        (x, y, z + 1),
+                        (x + 1, y, z + 1),
+                        (x + 1, y + 1, z + 1),
+                        (x, y + 1, z + 1),
+                    ],
+                    1.0,
+                ),
+                (
+                    [
+                        (x + 1, y, z),
+                        (x + 1, y + 1, z),
+                        (x + 1, y + 1, z + 1),
+                        (x + 1, y, z + 1),
+                    ],
+                    0.8,
+                ),
+                (
+                    [
+                        (x, y + 1, z),
+                        (x + 1, y + 1, z),
+                        (x + 1, y + 1, z + 1),
+                        (x, y + 1, z + 1),
+                    ],
+                    0.6,
+                ),
+            ];
+            for ((points, shade), visible) in faces.into_iter().zip(visible) {
+                if !visible {
+                    continue;
                 }
+                let fill = Color32::from_rgba_premultiplied(
+                    (color.r() as f32 * shade) as u8,
+                    (color.g() as f32 * shade) as u8,
+                    (color.b() as f32 * shade) as u8,
+                    color.a(),
+                );
+                painter.add(egui::Shape::convex_polygon(
+                    points.iter().map(|&(x, y, z)| to_screen(x, y, z)).collect(),
+                    fill,
+                    egui::Stroke::NONE,
+                ));
             }
         }
     }
-    None
-}
-
-fn is_128x128_png(data: &[u8]) -> bool {
-    if data.len() < 24 {
-        return false;
-    }
-    let width = u32::from_be_bytes([data[16], data[17], data[18], data[19]]);
-    let height = u32::from_be_bytes([data[20], data[21], data[22], data[23]]);
-    width == 128 && height == 128
-}
-
-pub struct BluePrint {
-    pub name: String,
-    pub description: String,
-    pub uuid: String,
-    pub path: PathBuf,
-    pub icon: Option<Vec<u8>>,
-}
-
-impl BluePrint {
-    pub fn from_name(name: &str, folder: &Path) -> Option<Self> {
-        if let Some((uuid, path, json)) = get_id_from_name(name, folder) {
-            let name = &json["name"].as_str().unwrap_or("err");
-            let description = &json["description"].as_str().unwrap_or("err");
-            return Some(Self {
-                name: name.to_string(),
-                description: description.to_string(),
-                uuid: uuid,
-                path: path,
-                icon: None,
-            });
-        }
-        None
-    }
 
-    pub fn from_uuid(uuid: String, bp_folder: PathBuf) -> Option<Self> {
-        let path = bp_folder.join(&uuid);
-        if let Ok(contents) = fs::read_to_string(&path.join("description.json")) {
-            if let Ok(json) = serde_json::from_str::<Value>(&contents) {
-                let name = &json["name"].as_str().unwrap_or("err");
-                let description = &json["description"].as_str().unwrap_or("err");
-                return Some(Self {
-                    name: name.to_string(),
-                    description: description.to_string(),
-                    uuid: uuid,
-                    path: path,
-                    icon: None,
-                });
+    fn draw_layer_preview(&mut self, ui: &mut Ui) {
+        let Some((min, max)) = self.export_preview.preview.bounds() else {
+            return;
+        };
+        ui.horizontal(|ui| {
+            ui.label("Layer (z):");
+            ui.add(egui::Slider::new(
+                &mut self.export_preview.layer,
+                min.2..=max.2,
+            ));
+        });
+        let (response, painter) =
+            ui.allocate_painter(vec2(ui.available_width(), PREVIEW_SIZE), Sense::hover());
+        let rect = response.rect;
+        painter.rect_filled(rect, 4.0, self.color_pallet.grid);
+        let (width, depth) = ((max.0 - min.0 + 1) as f32, (max.1 - min.1 + 1) as f32);
+        let cell = ((rect.width() - 16.0) / width).min((rect.height() - 16.0) / depth);
+        let origin = rect.center() - vec2(width, depth) * cell / 2.0;
+        let grid = egui::Rect::from_min_size(origin, vec2(width, depth) * cell);
+        painter.rect_stroke(
+            grid,
+            0.0,
+            egui::Stroke::new(1.0, self.color_pallet.grid_lines),
+            egui::StrokeKind::Outside,
+        );
+        let mut hovered = None;
+        for block in &self.export_preview.preview.blocks {
+            let (x, y, z) = block.cell;
+            if z != self.export_preview.layer {
+                continue;
+            }
+            let min_corner = origin + vec2((x - min.0) as f32, (y - min.1) as f32) * cell;
+            let block_rect = egui::Rect::from_min_size(min_corner, vec2(cell, cell)).shrink(0.5);
+            let color = match block.kind {
+                BlockKind::Glass => block.color.gamma_multiply(0.25),
+                _ => block.color,
+            };
+            painter.rect_filled(block_rect, 0.0, color);
+            if response
+                .hover_pos()
+                .is_some_and(|pos| block_rect.contains(pos))
+            {
+                hovered = Some(block.clone());
             }
         }
-        None
-    }
-
-    pub fn new(
-        name: String,
-        description: String,
-        bp_folder: PathBuf,
-        icon: Option<Vec<u8>>,
-    ) -> Self {
-        let uuid = Uuid::new_v4().to_string();
-        let final_icon = if icon.is_some() {
-            icon
-        } else {
-            Some(DEFAULT_ICON.to_vec())
-        };
-        Self {
-            name: name,
-            description: description,
-            uuid: uuid.clone(),
-            path: bp_folder.join(uuid),
-            icon: final_icon,
+        if let Some(block) = hovered {
+            let kind = match block.kind {
+                BlockKind::Gate => "Gate",
+                BlockKind::Timer => "Timer",
+                BlockKind::IO => "IO",
+                BlockKind::Glass => "Glass",
+            };
+            let (x, y, z) = block.cell;
+            response.on_hover_text(format!("{} at {}, {}, {}", kind, x, y, z));
         }
     }
 
-    pub fn export(
-        &mut self,
-        canvas: CanvasSnapshot,
-        toasts: &mut Toasts,
-        exporter_settings: &ExporterSettings,
-    ) {
-        let (
-            parts,
-            colors,
-            positions,
-            connections,
-            _id_remap,
-            _tunnels,
-            mut io_parts,
-            important_parts,
-            _port_sim_map,
-            _important_trees,
-        ) = get_canvas_raw_data(canvas, true, &Vec::new());
-
-        io_parts.extend(important_parts); // treat important parts as IO
-
-        let mut out_connections: Vec<Vec<usize>> = vec![Vec::new(); parts.len()];
-        for (from, to) in &connections {
-            out_connections[*from].push(*to);
+    pub fn draw_export_preview(&mut self, ctx: &egui::Context) {
+        if !self.export_preview.open {
+            return;
         }
-
-        let positioning = compute_positions(parts.len(), &io_parts, &positions, &exporter_settings);
-
-        let mut children: Vec<Value> = Vec::new();
-        for (i, part_type) in parts.iter().enumerate() {
-            let color = format!(
-                "{:02X}{:02X}{:02X}",
-                colors[i].r(),
-                colors[i].g(),
-                colors[i].b()
-            );
-            let controllers: Vec<Value> = out_connections[i]
-                .iter()
-                .map(|&id| json!({ "id": id }))
-                .collect();
-
-            let (px, py, pz) = positioning[i];
-            let is_io = io_parts.contains(&i);
-            let (xaxis, zaxis) = if is_io { (3, -2) } else { (2, 1) };
-
-            let child = match part_type {
-                PartType::Timer(buffer) => json!({
-                    "color": color,
-                    "controller": {
-                        "active": false,
-                        "controllers": controllers,
-                        "id": i,
-                        "joints": null,
-                        "seconds": buffer.len() / 40,
-                        "ticks": buffer.len() % 40
-                    },
-                    "pos": { "x": px, "y": py, "z": pz },
-                    "shapeId": "8f7fd0e7-c46e-4944-a414-7ce2437bb30f",
-                    "xaxis": 1,
-                    "zaxis": 3
-                }),
-                _ => {
-                    let mode = match part_type {
-                        PartType::And => 0,
-                        PartType::Or => 1,
-                        PartType::Xor => 2,
-                        PartType::Nand => 3,
-                        PartType::Nor => 4,
-                        PartType::Xnor => 5,
-                        _ => 0,
-                    };
-                    json!({
-                        "color": color,
-                        "controller": {
-                            "active": false,
-                            "controllers": controllers,
-                            "id": i,
-                            "joints": null,
-                            "mode": mode
-                        },
-                        "pos": { "x": px, "y": py, "z": pz },
-                        "shapeId": "9f0f56e8-2c31-4d83-996c-d00a9b296c3f",
-                        "xaxis": xaxis,
-                        "zaxis": zaxis
-                    })
+        let mut open = true;
+        let mut refresh = false;
+        egui::Window::new("Export Preview")
+            .open(&mut open)
+            .default_width(PREVIEW_SIZE)
+            .resizable(true)
+            .show(ctx, |ui| {
+                let preview = &self.export_preview.preview;
+                let (x, y, z) = preview.size();
+                ui.label(format!(
+                    "{} blocks: {} gates, {} timers, {} IO, {} glass",
+                    preview.blocks.len(),
+                    preview.count(BlockKind::Gate),
+                    preview.count(BlockKind::Timer),
+                    preview.count(BlockKind::IO),
+                    preview.count(BlockKind::Glass),
+                ));
+                ui.label(format!("Size: {} x {} x {}", x, y, z));
+                if preview.kept > 0 {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!("{} parts {}", preview.kept, KEPT_WARNING),
+                    );
                 }
-            };
-            children.push(child);
-        }
-
-        // glass backing behind IO parts
-        if !io_parts.is_empty() {
-            let min_y = io_parts
-                .iter()
-                .map(|&i| positioning[i].1)
-                .min()
-                .unwrap_or(0);
-            let min_z = io_parts
-                .iter()
-                .map(|&i| positioning[i].2)
-                .min()
-                .unwrap_or(0);
-            let max_y = io_parts
-                .iter()
-                .map(|&i| positioning[i].1)
-                .max()
-                .unwrap_or(0);
-            let max_z = io_parts
-                .iter()
-                .map(|&i| positioning[i].2)
-                .max()
-                .unwrap_or(0);
-            children.push(json!({
-                "bounds": { "x": 1, "y": max_y - min_y + 1, "z": max_z - min_z + 1 },
-                "color": "E4F8FF",
-                "pos": { "x": -1, "y": min_y - 1, "z": min_z },
-                "shapeId": "5f41af56-df4c-4837-9b3c-10781335757f",
-                "xaxis": 1,
-                "zaxis": 3
-            }));
-        }
-
-        let blueprint = json!({
-            "bodies": [{ "childs": children }],
-            "version": 4
-        });
-
-        _ = fs::create_dir_all(&self.path);
-
-        if let Ok(output) = serde_json::to_string(&blueprint) {
-            if fs::write(self.path.join("blueprint.json"), output).is_err() {
-                toasts.error("Failed to write to blueprint.json!");
-                return;
-            }
-        }
-
-        if let Ok(output) = serde_json::to_string(
-            &json!({ "description": self.description, "localId": self.uuid, "name": self.name, "type": "Blueprint", "version": 0}),
-        ) {
-            if fs::write(self.path.join("description.json"), output).is_err() {
-                toasts.error("Failed to write description.json");
-                if matches!(
-                    fs::exists(self.path.join("description.json")),
-                    Ok(true) | Err(_)
-                ) {
-                    _ = fs::remove_dir_all(&self.path);
-                    return;
+                ui.horizontal(|ui| {
+                    for view in PreviewView::VIEWS {
+                        ui.selectable_value(
+                            &mut self.export_preview.view,
+                            view.clone(),
+                            view.to_label(),
+                        );
+                    }
+                    if ui.button("Refresh").clicked() {
+                        refresh = true;
+                    }
+                });
+                ui.separator();
+                match self.export_preview.view {
+                    PreviewView::Isometric => self.draw_isometric_preview(ui),
+                    PreviewView::Layers => self.draw_layer_preview(ui),
                 }
-            }
+            });
+        if refresh {
+            self.refresh_export_preview();
         }
-        if let Some(icon) = self.icon.clone() {
-            if fs::write(self.path.join("icon.png"), icon).is_err() {
-                toasts.error("Failed to write icon.png");
-            }
+        if !open {
+            self.export_preview = ExportPreviewInputs::NEW;
         }
-        toasts.success("Exported blueprint!");
     }
 }
@@ -0,0 +1,119 @@
+use crate::state::AppState;
+use egui::Ui;
+use smlogic_core::error::ExportError;
+use smlogic_core::exporter::{BluePrint, list_blueprints};
+
+/// inputs for the open blueprint window
+pub struct ImportInputs {
+    pub open: bool,
+    pub filter: String,
+    pub blueprints: Vec<(String, String)>, // (name, uuid)
+}
+
+impl ImportInputs {
+    pub const NEW: Self = Self {
+        open: false,
+        filter: String::new(),
+        blueprints: Vec::new(),
+    };
+}
+
+impl AppState {
+    pub fn open_import_window(&mut self) {
+        let Some(bp_folder) = &self.bp_folder else {
+            self.toasts
+                .error(ExportError::NoBlueprintFolder.to_string());
+            return;
+        };
+        self.import_inputs.blueprints = list_blueprints(bp_folder);
+        self.import_inputs.open = true;
+    }
+
+    /// replaces the canvas with a blueprint from the blueprints folder
+    fn open_blueprint(&mut self, uuid: String) {
+        let Some(bp_folder) = self.bp_folder.clone() else {
+            return;
+        };
+        let Some(blueprint) = BluePrint::from_uuid(uuid, bp_folder) else {
+            self.toasts
+                .error(ExportError::BlueprintNotFound.to_string());
+            return;
+        };
+        match blueprint.import() {
+            Ok(snapshot) => {
+                self.end_simulation();
+                self.canvas_snapshot = snapshot;
+                self.current_module_path = None;
+                self.has_unsaved_changes = true;
+                self.reload_connection_counts();
+                self.toasts.success(format!(
+                    "Imported \"{}\" ({} parts)",
+                    blueprint.name,
+                    self.canvas_snapshot.parts.len()
+                ));
+            }
+            Err(e) => {
+                self.toasts.error(e.to_string());
+            }
+        }
+    }
+
+    fn draw_blueprint_list(&mut self, ui: &mut Ui) -> Option<String> {
+        ui.add(
+            egui::TextEdit::singleline(&mut self.import_inputs.filter)
+                .hint_text("search")
+                .desired_width(f32::INFINITY),
+        );
+        ui.separator();
+        if self.import_inputs.blueprints.is_empty() {
+            ui.label("No blueprints found.");
+            return None;
+        }
+        let filter = self.import_inputs.filter.to_lowercase();
+        let mut picked = None;
+        egui::ScrollArea::vertical()
+            .max_height(300.0)
+            .show(ui, |ui| {
+                for (name, uuid) in &self.import_inputs.blueprints {
+                    if !name.to_lowercase().contains(&filter) {
+                        continue;
+                    }
+                    if ui
+                        .selectable_label(false, name)
+                        .on_hover_text(uuid)
+                        .clicked()
+                    {
+                        picked = Some(uuid.clone());
+                    }
+                }
+            });
+        picked
+    }
+
+    pub fn draw_import_window(&mut self, ctx: &egui::Context) {
+        if !self.import_inputs.open {
+            return;
+        }
+        let mut open = true;
+        let mut picked = None;
+        egui::Window::new("Open Blueprint")
+            .open(&mut open)
+            .default_width(250.0)
+            .resizable(true)
+            .frame(
+                egui::Frame::new()
+                    .fill(self.color_pallet.base)
+                    .inner_margin(8.0),
+            )
+            .show(ctx, |ui| {
+                picked = self.draw_blueprint_list(ui);
+            });
+        if let Some(uuid) = picked {
+            open = false;
+            self.open_blueprint(uuid);
+        }
+        if !open {
+            self.import_inputs = ImportInputs::NEW;
+        }
+    }
+}
@@ -117,9 +117,11 @@ impl AppState {
         }
 
         // backspace
+        // parts can be selected while simulating (jumping to them), but not deleted
         if (ctx.input(|i| i.key_pressed(Key::Backspace))
             | ctx.input(|i| i.key_pressed(Key::Delete)))
             && !ctx.wants_keyboard_input()
+            && self.sim_state.is_none()
         {
             if !self.selection.is_empty() {
                 self.push_undo();
@@ -150,7 +152,7 @@ impl AppState {
         }
 
         // undo/redo
-        if ctx.input(|i| i.key_pressed(Key::Z)) {
+        if ctx.input(|i| i.key_pressed(Key::Z)) && self.sim_state.is_none() {
             if ctrl_held && shift_held {
                 self.redo()
             } else if ctrl_held {
@@ -173,7 +175,7 @@ impl AppState {
                 self.current_module_path.clone()
             };
             if let Some(path) = file {
-                if let Err(e) = self.canvas_snapshot.save(path.clone()) {
+                if let Err(e) = self.top_canvas().save(path.clone()) {
                     self.toasts.error(format!("Failed to save: {}", e));
                 } else {
                     self.has_unsaved_changes = false;
@@ -222,6 +224,21 @@ impl AppState {
         };
         let world_pos = self.screen_to_world(screen_pos);
 
+        // double clicking a module while simulating shows whats inside it
+        if self.active_tool == Some(Tool::Simulator)
+            && response.double_clicked()
+            && let Some(part) = self.part_at_pos(world_pos)
+            && matches!(part.part_data, PartData::Module(_))
+        {
+            let mut path = self
+                .module_view
+                .as_ref()
+                .map(|view| view.path.clone())
+                .unwrap_or_default();
+            path.push(part.id);
+            self.module_view_request = Some(path);
+        }
+
         match &self.interaction_state {
             InteractionState::Idle => {
                 if ctx.input(|i| i.pointer.button_pressed(PointerButton::Secondary))
@@ -233,6 +250,7 @@ impl AppState {
                 }
                 if ctx.input(|i| i.pointer.button_pressed(PointerButton::Middle))
                     && self.config.middle_click_deletes
+                    && self.module_view.is_none()
                 {
                     if let Some(part_id) = self.part_at_pos(world_pos).map(|p| p.id) {
                         self.push_undo();
@@ -246,7 +264,9 @@ impl AppState {
                 }
 
                 if ctx.input(|i| i.pointer.button_pressed(PointerButton::Primary)) {
-                    let selected_resize = self.resize_at_pos(world_pos);
+                    let selected_resize = self
+                        .resize_at_pos(world_pos)
+                        .filter(|_| self.module_view.is_none());
                     let selected_port = self.port_at_pos(world_pos);
                     let selected_connection = self.connection_at_pos(world_pos);
                     let selected_part = self.part_at_pos(world_pos).map(|p| p.id);
@@ -400,7 +420,7 @@ impl AppState {
                 }
             }
             InteractionState::Connecting(connect_start) => {
-                if let Some(start_pos) = connect_start.pos(self) {
+                if let Some(start_pos) = connect_start.pos(&self.canvas_snapshot) {
                     if connect_start.input {
                         draw_connection(self, world_pos, start_pos, painter, false, false);
                     } else {
@@ -459,8 +479,16 @@ impl AppState {
                 .copied()
                 .flatten();
 
+            let step_back_key = self
+                .config
+                .keybinds
+                .get("simulator step back")
+                .copied()
+                .flatten();
+
             let mut do_pause = false;
             let mut do_tick = false;
+            let mut do_step_back = false;
 
             ctx.input_mut(|i| {
                 if pause_key.is_some_and(|k| i.consume_key(egui::Modifiers::NONE, k)) {
@@ -469,6 +497,9 @@ impl AppState {
                 if tick_key.is_some_and(|k| i.consume_key(egui::Modifiers::NONE, k)) {
                     do_tick = true;
                 }
+                if step_back_key.is_some_and(|k| i.consume_key(egui::Modifiers::NONE, k)) {
+                    do_step_back = true;
+                }
             });
 
             let consumed_a_focus_key = FOCUS_KEYS
@@ -490,6 +521,12 @@ impl AppState {
                     state.step = true;
                 }
             }
+            if do_step_back && let Some(sim_state) = &self.sim_state {
+                let mut state = sim_state.lock();
+                if !state.running {
+                    state.step_back();
+                }
+            }
         }
     }
 }
@@ -16,17 +16,15 @@ pub struct LuaScript {
     pub output: String,
 }
 
-impl PartType {
-    fn gate_from_string(gate_type: String) -> mlua::Result<Self> {
-        match gate_type.to_lowercase().as_str() {
-            "and" => Ok(PartType::And),
-            "or" => Ok(PartType::Or),
-            "xor" => Ok(PartType::Xor),
-            "nand" => Ok(PartType::Nand),
-            "nor" => Ok(PartType::Nor),
-            "xnor" => Ok(PartType::Xnor),
-            _ => return Err(mlua::Error::runtime("invalid gate type")),
-        }
+fn gate_from_string(gate_type: String) -> mlua::Result<PartType> {
+    match gate_type.to_lowercase().as_str() {
+        "and" => Ok(PartType::And),
+        "or" => Ok(PartType::Or),
+        "xor" => Ok(PartType::Xor),
+        "nand" => Ok(PartType::Nand),
+        "nor" => Ok(PartType::Nor),
+        "xnor" => Ok(PartType::Xnor),
+        _ => return Err(mlua::Error::runtime("invalid gate type")),
     }
 }
 
@@ -42,6 +40,13 @@ fn get_color(opts: &Option<mlua::Table>) -> mlua::Result<Color32> {
     };
 }
 
+/// the `active` option, wether the part starts on
+fn get_active(opts: &Option<mlua::Table>) -> bool {
+    opts.as_ref()
+        .and_then(|t| t.get::<bool>("active").ok())
+        .unwrap_or(false)
+}
+
 fn set_opts(opts: &Option<mlua::Table>, part: &mut Part) -> mlua::Result<()> {
     let x_opt = opts.as_ref().and_then(|t| t.get::<f32>("x").ok());
     let y_opt = opts.as_ref().and_then(|t| t.get::<f32>("y").ok());
@@ -91,11 +96,13 @@ fn get_part(part: &Part, lua: &Lua) -> mlua::Result<mlua::Table> {
         PartData::Gate(gate) => {
             t.set("type", gate.gate_type.to_label())?;
             t.set("important", gate.important)?;
+            t.set("active", gate.active)?;
         }
         PartData::Timer(timer) => {
             t.set("type", "timer")?;
             t.set("seconds", timer.secs)?;
             t.set("ticks", timer.ticks)?;
+            t.set("active", timer.active)?;
         }
         PartData::IO(io) => {
             t.set("type", "io")?;
@@ -184,10 +191,11 @@ impl AppState {
                                 .as_ref()
                                 .and_then(|t| t.get::<bool>("important").ok())
                                 .unwrap_or(false);
-                            let part_type = PartType::gate_from_string(gate_type)?;
+                            let active = get_active(&opts);
+                            let part_type = gate_from_string(gate_type)?;
                             let label = opts.as_ref().and_then(|t| t.get::<String>("label").ok());
                             let mut app = app_cell.borrow_mut();
-                            let id = Part::new(part_type, &mut **app, get_position(x, y));
+                            let id = app.add_part(part_type, get_position(x, y));
                             if let Some(part) = app.canvas_snapshot.parts.get_mut(&id) {
                                 part.color = color;
                                 if let Some(l) = label {
@@ -195,6 +203,7 @@ impl AppState {
                                 }
                                 if let PartData::Gate(data) = &mut part.part_data {
                                     data.important = important;
+                                    data.active = active;
                                 }
                             }
                             Ok(id)
@@ -207,6 +216,7 @@ impl AppState {
                         let important_opt =
                             opts.as_ref().and_then(|t| t.get::<bool>("important").ok());
                         let type_opt = opts.as_ref().and_then(|t| t.get::<String>("type").ok());
+                        let active_opt = opts.as_ref().and_then(|t| t.get::<bool>("active").ok());
                         let mut app = app_cell.borrow_mut();
                         if let Some(part) = app.canvas_snapshot.parts.get_mut(&id) {
                             set_opts(&opts, part)?;
@@ -214,10 +224,12 @@ impl AppState {
                                 if let Some(important) = important_opt {
                                     data.important = important;
                                 }
+                                if let Some(active) = active_opt {
+                                    data.active = active;
+                                }
                                 if let Some(gate_type) = type_opt {
-                                    data.gate_type = GateType::from_part_type(
-                                        PartType::gate_from_string(gate_type)?,
-                                    );
+                                    data.gate_type =
+                                        GateType::from_part_type(gate_from_string(gate_type)?);
                                 }
                             } else {
                                 return Err(mlua::Error::runtime("not a gate!"));
@@ -234,8 +246,9 @@ impl AppState {
                         |_, (secs, ticks, x, y, opts): (u8, u8, f32, f32, Option<mlua::Table>)| {
                             let color = get_color(&opts)?;
                             let label = opts.as_ref().and_then(|t| t.get::<String>("label").ok());
+                            let active = get_active(&opts);
                             let mut app = app_cell.borrow_mut();
-                            let id = Part::new(PartType::Timer, &mut **app, get_position(x, y));
+                            let id = app.add_part(PartType::Timer, get_position(x, y));
                             if let Some(part) = app.canvas_snapshot.parts.get_mut(&id) {
                                 part.color = color;
                                 if let Some(l) = label {
@@ -244,6 +257,7 @@ impl AppState {
                                 if let PartData::Timer(data) = &mut part.part_data {
                                     data.secs = secs;
                                     data.ticks = ticks;
+                                    data.active = active;
                                 }
                             }
                             Ok(id)
@@ -255,6 +269,7 @@ impl AppState {
                     scope.create_function_mut(|_, (id, opts): (u64, Option<mlua::Table>)| {
                         let secs_opt = opts.as_ref().and_then(|t| t.get::<u8>("seconds").ok());
                         let ticks_opt = opts.as_ref().and_then(|t| t.get::<u8>("ticks").ok());
+                        let active_opt = opts.as_ref().and_then(|t| t.get::<bool>("active").ok());
                         let mut app = app_cell.borrow_mut();
                         if let Some(part) = app.canvas_snapshot.parts.get_mut(&id) {
                             set_opts(&opts, part)?;
@@ -265,6 +280,9 @@ impl AppState {
                                 if let Some(ticks) = ticks_opt {
                                     data.ticks = ticks;
                                 }
+                                if let Some(active) = active_opt {
+                                    data.active = active;
+                                }
                             } else {
                                 return Err(mlua::Error::runtime("not a timer!"));
                             }
@@ -281,7 +299,7 @@ impl AppState {
                             let color = get_color(&opts)?;
                             let label = opts.as_ref().and_then(|t| t.get::<String>("label").ok());
                             let mut app = app_cell.borrow_mut();
-                            let id = Part::new(PartType::Input, &mut **app, get_position(x, y));
+                            let id = app.add_part(PartType::Input, get_position(x, y));
                             if let Some(part) = app.canvas_snapshot.parts.get_mut(&id) {
                                 part.color = color;
                                 if let Some(l) = label {
@@ -299,7 +317,7 @@ impl AppState {
                             let color = get_color(&opts)?;
                             let label = opts.as_ref().and_then(|t| t.get::<String>("label").ok());
                             let mut app = app_cell.borrow_mut();
-                            let id = Part::new(PartType::Output, &mut **app, get_position(x, y));
+                            let id = app.add_part(PartType::Output, get_position(x, y));
                             if let Some(part) = app.canvas_snapshot.parts.get_mut(&id) {
                                 part.color = color;
                                 if let Some(l) = label {
@@ -316,7 +334,7 @@ impl AppState {
                         |_, (label, x, y, opts): (String, f32, f32, Option<mlua::Table>)| {
                             let color = get_color(&opts)?;
                             let mut app = app_cell.borrow_mut();
-                            let id = Part::new(PartType::Label, &mut **app, get_position(x, y));
+                            let id = app.add_part(PartType::Label, get_position(x, y));
                             if let Some(part) = app.canvas_snapshot.parts.get_mut(&id) {
                                 part.color = color;
                                 part.label = label
@@ -1,15 +1,26 @@
+mod breakpoints;
+mod buses;
 mod canvas;
+mod cli;
 mod colors;
 mod connections;
 mod exporter;
+mod importer;
 mod interaction;
 mod lua_scripting;
+mod memory;
+mod module_view;
+mod oscillation;
 mod part_render;
 mod parts;
+mod probes;
 mod saveload;
 mod simulator;
 mod state;
+mod stimulus;
+mod testing;
 mod tools;
+mod waveform;
 
 use eframe::egui::{self};
 use std::time::Instant;
@@ -17,6 +28,11 @@ use std::time::Instant;
 use state::AppState;
 
 fn main() -> eframe::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
+
     let icon = eframe::icon_data::from_png_bytes(include_bytes!("../assets/icon.png"))
         .expect("Failed to load app icon");
 
@@ -71,13 +87,27 @@ impl eframe::App for AppState {
         self.draw_sidebar(ctx);
         self.draw_settings(ctx);
         self.draw_lua_script(ctx);
+        self.draw_test_results(ctx);
+        self.draw_import_window(ctx);
+        self.draw_export_preview(ctx);
         self.draw_footer(ctx);
+        if self.waveform_open && self.active_tool == Some(tools::Tool::Simulator) {
+            self.draw_waveform_panel(ctx);
+        }
+        if self.active_tool == Some(tools::Tool::Simulator) {
+            self.draw_memory_window(ctx);
+        }
         if !self.important_gates.is_empty() {
             self.draw_important_sidebar(ctx);
         }
         egui::CentralPanel::default().show(ctx, |ui| {
+            self.show_module_view();
             let (response, painter) = self.draw_canvas(ui, ctx);
             self.handle_input(ctx, &painter, &response);
+            self.hide_module_view();
         });
+        if let Some(path) = self.module_view_request.take() {
+            self.open_module_view(path);
+        }
     }
 }
@@ -0,0 +1,232 @@
+use crate::parts::PartData;
+use crate::state::{AppState, Selection};
+use egui::{Key, RichText, Ui};
+use smlogic_core::bus::BusFormat;
+use smlogic_core::memory::{Memory, word_values};
+
+const LABEL_WORDS_HELP: &str = "Every label is one word, ie gates called r0 then r1";
+
+/// inputs for the memory sections of the sidebar and the memory window
+pub struct MemoryInputs {
+    pub name: String,
+    pub open: Option<(usize, Vec<Option<Vec<usize>>>)>, // memory index, simulation indexes per word
+    pub editing: Option<(usize, String)>,               // word being typed into
+}
+
+impl MemoryInputs {
+    pub const NEW: Self = Self {
+        name: String::new(),
+        open: None,
+        editing: None,
+    };
+}
+
+impl AppState {
+    fn add_memory(&mut self, from_modules: bool) {
+        let ids: Vec<u64> = self
+            .selection
+            .iter()
+            .filter_map(|s| match s {
+                Selection::Part(id) => Some(*id),
+                _ => None,
+            })
+            .collect();
+        let name = if self.memory_inputs.name.is_empty() {
+            format!("memory {}", self.canvas_snapshot.memories.len() + 1)
+        } else {
+            std::mem::take(&mut self.memory_inputs.name)
+        };
+        let memory = if from_modules {
+            Memory::from_modules(name, &self.canvas_snapshot, &ids)
+        } else {
+            Memory::from_labels(name, &self.canvas_snapshot, &ids)
+        };
+        if memory.words.is_empty() {
+            self.toasts.error("Nothing selected can be used as a word");
+            return;
+        }
+        self.push_undo();
+        self.canvas_snapshot.memories.push(memory);
+    }
+
+    /// lists the modules memories and makes new ones out of the selection, when not simulating
+    pub fn draw_memory_editor(&mut self, ui: &mut Ui) {
+        let selected: Vec<&PartData> = self
+            .selection
+            .iter()
+            .filter_map(|s| match s {
+                Selection::Part(id) => self.canvas_snapshot.parts.get(id),
+                _ => None,
+            })
+            .map(|p| &p.part_data)
+            .collect();
+        let modules = selected
+            .iter()
+            .filter(|p| matches!(p, PartData::Module(_)))
+            .count();
+        let gates = selected
+            .iter()
+            .filter(|p| matches!(p, PartData::Gate(_) | PartData::Timer(_) | PartData::IO(_)))
+            .count();
+        if self.canvas_snapshot.memories.is_empty() && modules < 2 && gates < 2 {
+            return;
+        }
+        ui.separator();
+        ui.heading("Memory");
+        let mut remove = None;
+        for (i, memory) in self.canvas_snapshot.memories.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.small_button("x").clicked() {
+                    remove = Some(i);
+                }
+                ui.label(format!("{} ({} words)", memory.name, memory.words.len()));
+            });
+        }
+        if let Some(i) = remove {
+            self.push_undo();
+            self.canvas_snapshot.memories.remove(i);
+        }
+
+        if modules < 2 && gates < 2 {
+            return;
+        }
+        ui.add(
+            egui::TextEdit::singleline(&mut self.memory_inputs.name)
+                .hint_text("memory name")
+                .desired_width(120.0),
+        );
+        if modules >= 2 && ui.button("Make memory (word per module)").clicked() {
+            self.add_memory(true);
+        }
+        if gates >= 2
+            && ui
+                .button("Make memory (word per label)")
+                .on_hover_text(LABEL_WORDS_HELP)
+                .clicked()
+        {
+            self.add_memory(false);
+        }
+    }
+
+    /// buttons to open each memory while simulating
+    pub fn draw_memories(&mut self, ui: &mut Ui) {
+        if self.canvas_snapshot.memories.is_empty() {
+            return;
+        }
+        ui.separator();
+        ui.label("Memory:");
+        let mut open = None;
+        for (i, memory) in self.canvas_snapshot.memories.iter().enumerate() {
+            let text = format!("{} ({} words)", memory.name, memory.words.len());
+            if ui.button(text).clicked() {
+                open = Some((i, memory.indexes(&self.probe_indexes)));
+            }
+        }
+        if open.is_some() {
+            self.memory_inputs.open = open;
+            self.memory_inputs.editing = None;
+        }
+    }
+
+    /// hex dump of the open memory, clicking a word lets you type a new value into it
+    pub fn draw_memory_window(&mut self, ctx: &egui::Context) {
+        let Some((memory_i, indexes)) = &self.memory_inputs.open else {
+            return;
+        };
+        let (Some(memory), Some(sim_state)) = (
+            self.canvas_snapshot.memories.get(*memory_i),
+            self.sim_state.clone(),
+        ) else {
+            self.memory_inputs.open = None;
+            return;
+        };
+        let width = memory.word_width();
+        let digits = width.div_ceil(4);
+        let per_row = if width <= 8 { 16 } else { 8 };
+        let outputs = self.sim_state_outputs_snapshot.clone().unwrap_or_default();
+        let values = word_values(indexes, &outputs);
+        let editing = &mut self.memory_inputs.editing;
+        let mut write = None;
+        let mut invalid = None;
+        let mut open = true;
+
+        egui::Window::new(&memory.name)
+            .id(egui::Id::new("memory_window"))
+            .open(&mut open)
+            .resizable(true)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    egui::Grid::new("memory_grid")
+                        .spacing(egui::vec2(6.0, 2.0))
+                        .show(ui, |ui| {
+                            for (row, words) in values.chunks(per_row).enumerate() {
+                                let start = row * per_row;
+                                ui.label(
+                                    RichText::new(format!("{:04X}", start)).monospace().weak(),
+                                );
+                                for (i, value) in words.iter().enumerate() {
+                                    let word = start + i;
+                                    if let Some((_, text)) =
+                                        editing.as_mut().filter(|(w, _)| *w == word)
+                                    {
+                                        let response = ui.add(
+                                            egui::TextEdit::singleline(text)
+                                                .font(egui::TextStyle::Monospace)
+                                                .desired_width(digits as f32 * 9.0 + 4.0),
+                                        );
+                                        response.request_focus();
+                                        if ui.input(|i| i.key_pressed(Key::Enter)) {
+                                            match BusFormat::Hex.parse(text, width) {
+                                                Some(value) => write = Some((word, value)),
+                                                None => invalid = Some(text.clone()),
+                                            }
+                                            *editing = None;
+                                        } else if ui.input(|i| i.key_pressed(Key::Escape)) {
+                                            *editing = None;
+                                        }
+                                        continue;
+                                    }
+                                    let text = match value {
+                                        Some(value) => format!("{:0digits$X}", value),
+                                        None => "?".repeat(digits),
+                                    };
+                                    let response = ui.add(
+                                        egui::Label::new(RichText::new(&text).monospace())
+                                            .sense(egui::Sense::click()),
+                                    );
+                                    if response.clicked() && value.is_some() {
+                                        *editing = Some((word, text));
+                                    }
+                                }
+                                if width <= 8 {
+                                    // ascii column like a normal hex editor
+                                    let ascii: String = words
+                                        .iter()
+                                        .map(|v| match v.map(|v| v as u8) {
+                                            Some(c @ 0x20..=0x7e) => c as char,
+                                            _ => '.',
+                                        })
+                                        .collect();
+                                    ui.label(RichText::new(ascii).monospace().weak());
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+            });
+
+        if let Some((word, value)) = write
+            && let Some(Some(bits)) = indexes.get(word)
+        {
+            sim_state.lock().write_word(bits, value);
+        }
+        if let Some(text) = invalid {
+            self.toasts
+                .error(format!("\"{}\" isnt a valid {} bit hex value", text, width));
+        }
+        if !open {
+            self.memory_inputs.open = None;
+            self.memory_inputs.editing = None;
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use crate::parts::PartData;
+use crate::state::{AppState, CanvasSnapshot};
+use egui::{Pos2, Rect, Ui, Vec2};
+
+/// looking inside a module instance while simulating. the inner canvas gets swapped into
+/// `canvas_snapshot` only while the canvas is drawn and clicked on, so everything else (saving,
+/// the sidebar, ...) still sees the real module
+pub struct ModuleView {
+    pub path: Vec<u64>, // module ids from the top level down
+    pub labels: Vec<String>,
+    pub canvas: CanvasSnapshot, // whichever canvas isnt in `canvas_snapshot` right now
+    pub shown: bool,            // if the inner canvas is the one in `canvas_snapshot`
+    pub views: Vec<(Vec2, f32)>, // pan and zoom of each level above, to go back to
+}
+
+impl AppState {
+    /// swaps the inner canvas in, called before drawing the canvas
+    pub fn show_module_view(&mut self) {
+        if let Some(view) = &mut self.module_view
+            && !view.shown
+        {
+            std::mem::swap(&mut self.canvas_snapshot, &mut view.canvas);
+            view.shown = true;
+        }
+    }
+
+    /// puts the real module back into `canvas_snapshot`
+    pub fn hide_module_view(&mut self) {
+        if let Some(view) = &mut self.module_view
+            && view.shown
+        {
+            std::mem::swap(&mut self.canvas_snapshot, &mut view.canvas);
+            view.shown = false;
+        }
+    }
+
+    /// the module thats actually open, even if the canvas is showing a module instance
+    pub fn top_canvas(&self) -> &CanvasSnapshot {
+        match &self.module_view {
+            Some(view) if view.shown => &view.canvas,
+            _ => &self.canvas_snapshot,
+        }
+    }
+
+    pub fn close_module_view(&mut self) {
+        self.hide_module_view();
+        if let Some(view) = self.module_view.take()
+            && let Some(&(pan_offset, zoom)) = view.views.first()
+        {
+            self.pan_offset = pan_offset;
+            self.zoom = zoom;
+        }
+    }
+
+    /// shows the inside of the module instance at `path` (module ids from the top level down)
+    /// with live values from the simulation. an empty path goes back to the top level
+    pub fn open_module_view(&mut self, path: Vec<u64>) {
+        self.hide_module_view();
+        if path.is_empty() {
+            self.close_module_view();
+            return;
+        }
+        let mut canvas = &self.canvas_snapshot;
+        let mut labels = Vec::new();
+        for id in &path {
+            let Some(part) = canvas.parts.get(id) else {
+                return;
+            };
+            let PartData::Module(module) = &part.part_data else {
+                return;
+            };
+            labels.push(part.label.clone());
+            canvas = &module.canvas_snapshot;
+        }
+        let mut canvas = canvas.clone();
+        for part in canvas.parts.values_mut() {
+            let mut part_path = path.clone();
+            part_path.push(part.id);
+            part.simulation_index = match part.part_data {
+                PartData::Module(_) => None,
+                _ => self.probe_indexes.get(&part_path).copied(),
+            };
+        }
+        // wires go by whatever drives them, module outputs are [module, output io]
+        for connection in canvas.connections.iter_mut() {
+            let mut start_path = path.clone();
+            start_path.push(connection.start.part);
+            start_path.extend(connection.start.port_id);
+            connection.simulation_index = self.probe_indexes.get(&start_path).copied();
+        }
+
+        let (old_path, mut views) = self
+            .module_view
+            .take()
+            .map(|view| (view.path, view.views))
+            .unwrap_or_default();
+        if path.len() < old_path.len() && old_path.starts_with(&path) {
+            // going back up, so theres a view to go back to
+            (self.pan_offset, self.zoom) = views[path.len()];
+            views.truncate(path.len());
+        } else if path != old_path {
+            let shared = path
+                .iter()
+                .zip(&old_path)
+                .take_while(|(a, b)| a == b)
+                .count();
+            views.truncate(shared);
+            views.resize(path.len(), (self.pan_offset, self.zoom));
+            let bounds = canvas.parts.values().fold(Rect::NOTHING, |rect, part| {
+                rect.union(Rect::from_min_size(part.pos, part.part_data.size()))
+            });
+            if bounds.is_positive() {
+                self.center_view_on(bounds.center());
+            }
+        }
+        self.selection.clear();
+        self.module_view = Some(ModuleView {
+            path,
+            labels,
+            canvas,
+            shown: false,
+            views,
+        });
+    }
+
+    pub fn center_view_on(&mut self, world_pos: Pos2) {
+        self.pan_offset = world_pos.to_vec2() - self.canvas_rect.center().to_vec2() / self.zoom;
+    }
+
+    /// "Top > adder > full adder" buttons over the canvas, for going back up
+    pub fn draw_module_breadcrumbs(&mut self, ctx: &egui::Context) {
+        let Some(view) = &self.module_view else {
+            return;
+        };
+        let mut go_to = None;
+        egui::Area::new(egui::Id::new("module_breadcrumbs"))
+            .fixed_pos(self.canvas_rect.left_top() + Vec2::splat(8.0))
+            .show(ctx, |ui: &mut Ui| {
+                egui::Frame::new()
+                    .fill(self.color_pallet.base)
+                    .inner_margin(6.0)
+                    .corner_radius(4.0)
+                    .show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            if ui.button("Top").clicked() {
+                                go_to = Some(Vec::new());
+                            }
+                            for (i, label) in view.labels.iter().enumerate() {
+                                ui.label(">");
+                                if i + 1 == view.labels.len() {
+                                    ui.strong(label);
+                                } else if ui.button(label).clicked() {
+                                    go_to = Some(view.path[..=i].to_vec());
+                                }
+                            }
+                            ui.weak("(read only)");
+                        });
+                    });
+            });
+        if go_to.is_some() {
+            self.module_view_request = go_to;
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use crate::state::{AppState, Selection};
+use egui::Ui;
+use smlogic_core::oscillation::{DEFAULT_WINDOW, OscillationDetector};
+
+const WINDOW_HELP: &str = "How many ticks in a row a part has to change before it's listed";
+
+impl AppState {
+    /// centers the view on a part and selects it, opening up the modules its in
+    pub fn jump_to_part(&mut self, path: &[u64]) {
+        let Some((&part_id, modules)) = path.split_last() else {
+            return;
+        };
+        self.open_module_view(modules.to_vec());
+        let canvas = match &self.module_view {
+            Some(view) => &view.canvas,
+            None => &self.canvas_snapshot,
+        };
+        let Some(part) = canvas.parts.get(&part_id) else {
+            return;
+        };
+        self.center_view_on(part.pos + part.part_data.size() / 2.0);
+        self.selection = vec![Selection::Part(part_id)];
+    }
+
+    /// the oscillation section of the simulator sidebar
+    pub fn draw_oscillation(&mut self, ui: &mut Ui) {
+        let Some(sim_state) = self.sim_state.clone() else {
+            return;
+        };
+        ui.separator();
+        let (oscillating, cycle) = {
+            let mut state = sim_state.lock();
+            let mut enabled = state.oscillation.is_some();
+            ui.horizontal(|ui| {
+                if ui.checkbox(&mut enabled, "Detect oscillation").changed() {
+                    state.oscillation = enabled.then(|| OscillationDetector::new(DEFAULT_WINDOW));
+                }
+                if let Some(detector) = &mut state.oscillation {
+                    ui.add(
+                        egui::DragValue::new(&mut detector.window)
+                            .range(2..=10_000)
+                            .suffix(" ticks"),
+                    )
+                    .on_hover_text(WINDOW_HELP);
+                }
+            });
+            let Some(detector) = &state.oscillation else {
+                return;
+            };
+            let oscillating: Vec<Vec<u64>> = detector
+                .oscillating
+                .iter()
+                .filter_map(|&i| state.part_paths.get(i).cloned())
+                .collect();
+            (oscillating, detector.cycle)
+        };
+
+        if let Some((period, since)) = cycle {
+            ui.small(format!(
+                "whole circuit repeats every {} ticks (since tick {})",
+                period, since
+            ));
+        }
+        if oscillating.is_empty() {
+            ui.small("nothing is oscillating");
+            return;
+        }
+        ui.label(format!("Oscillating parts: {}", oscillating.len()));
+        let mut jump = None;
+        egui::ScrollArea::vertical()
+            .id_salt("oscillating_parts")
+            .max_height(200.0)
+            .show(ui, |ui| {
+                for path in &oscillating {
+                    let labels = self.canvas_snapshot.path_labels(path);
+                    ui.horizontal(|ui| {
+                        if ui.small_button("Go").clicked() {
+                            jump = Some(path.clone());
+                        }
+                        // parts inside modules show which modules they're in
+                        ui.label(labels.join(" > "));
+                    });
+                }
+            });
+        if let Some(path) = jump {
+            self.jump_to_part(&path);
+        }
+    }
+}
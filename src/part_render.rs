@@ -19,9 +19,22 @@ const CONNECTION_COUNT_SIZE: f32 = 10.0;
 const CONNECTION_LABEL_SHIFT: Vec2 = Vec2::new(8.0, 7.0);
 const CONNECTION_LABEL_SIZE: f32 = 7.0;
 
+const STARTS_ON_HELP: &str =
+    "On from the first tick of the simulation, and exported as active so it starts on in game";
+
 const OUTPUT_COLOR: Color32 = Color32::from_rgb(255, 0, 0);
 const INPUT_COLOR: Color32 = Color32::from_rgb(0, 255, 0);
 
+/// rendering for the different part types, the parts themselves live in smlogic-core
+pub trait DrawPart {
+    fn draw(&self, part: &Part, painter: &Painter, app_state: &AppState);
+}
+
+/// the properties section of the sidebar for the selected part
+pub trait PartProperties {
+    fn draw_properties(&mut self, ui: &mut Ui, app_state: &mut AppState);
+}
+
 impl AppState {
     pub fn draw_parts(&self, painter: &Painter) {
         for part in self.canvas_snapshot.parts.values() {
@@ -115,7 +128,7 @@ pub fn draw_part_base(
     }
 
     for port in ports {
-        if let Some(pos) = port.pos(app_state) {
+        if let Some(pos) = port.pos(&app_state.canvas_snapshot) {
             painter.circle_filled(
                 app_state.world_to_screen(pos),
                 PORT_SIZE * app_state.zoom,
@@ -150,8 +163,8 @@ pub fn draw_part_base(
     }
 }
 
-impl Gate {
-    pub fn draw(&self, part: &Part, painter: &Painter, app_state: &AppState) {
+impl DrawPart for Gate {
+    fn draw(&self, part: &Part, painter: &Painter, app_state: &AppState) {
         // skip rendering if off-screen
         let screen_rect = Rect::from_min_max(
             app_state.world_to_screen(part.pos),
@@ -170,7 +183,7 @@ impl Gate {
                     .get(i)
                     .copied()
             })
-            .unwrap_or(false);
+            .unwrap_or(self.active); // shows what it starts as when not simulating
 
         //draw main base & outline
         draw_part_base(
@@ -301,35 +314,37 @@ impl Gate {
             }
         }
     }
+}
 
-    pub fn draw_properties(&mut self, ui: &mut Ui, app_state: &mut AppState, label: &mut String) {
-        ui.horizontal(|ui| {
-            ui.label("Type: ");
-            egui::ComboBox::from_id_salt("gate_type_combo")
-                .selected_text(self.gate_type.to_label())
-                .show_ui(ui, |ui| {
-                    for lgt in GateType::TYPES {
-                        if ui
-                            .selectable_label(lgt.clone() == self.gate_type, lgt.to_label())
-                            .clicked()
-                        {
-                            if lgt.clone() != self.gate_type {
-                                app_state.push_undo();
-                                if *label == self.gate_type.to_label() {
-                                    *label = lgt.to_label();
-                                }
-                                self.gate_type = lgt.clone();
+fn gate_properties(gate: &mut Gate, ui: &mut Ui, app_state: &mut AppState, label: &mut String) {
+    ui.horizontal(|ui| {
+        ui.label("Type: ");
+        egui::ComboBox::from_id_salt("gate_type_combo")
+            .selected_text(gate.gate_type.to_label())
+            .show_ui(ui, |ui| {
+                for lgt in GateType::TYPES {
+                    if ui
+                        .selectable_label(lgt.clone() == gate.gate_type, lgt.to_label())
+                        .clicked()
+                    {
+                        if lgt.clone() != gate.gate_type {
+                            app_state.push_undo();
+                            if *label == gate.gate_type.to_label() {
+                                *label = lgt.to_label();
                             }
+                            gate.gate_type = lgt.clone();
                         }
                     }
-                });
-        });
-        ui.checkbox(&mut self.important, "Important");
-    }
+                }
+            });
+    });
+    ui.checkbox(&mut gate.important, "Important");
+    ui.checkbox(&mut gate.active, "Starts on")
+        .on_hover_text(STARTS_ON_HELP);
 }
 
-impl Timer {
-    pub fn draw(&self, part: &Part, painter: &Painter, app_state: &AppState) {
+impl DrawPart for Timer {
+    fn draw(&self, part: &Part, painter: &Painter, app_state: &AppState) {
         // skip rendering if off screen
         let screen_rect = Rect::from_min_max(
             app_state.world_to_screen(part.pos),
@@ -348,7 +363,7 @@ impl Timer {
                     .get(i)
                     .copied()
             })
-            .unwrap_or(false);
+            .unwrap_or(self.active); // shows what it starts as when not simulating
 
         //draw main base & outline
         draw_part_base(
@@ -399,34 +414,36 @@ impl Timer {
         painter.line_segment([top_left, top_right], stroke);
         painter.line_segment([bottom_left, bottom_right], stroke);
     }
+}
 
-    pub fn draw_properties(&mut self, ui: &mut Ui, app_state: &mut AppState) {
-        let mut secs = self.secs;
-        let mut ticks = self.ticks;
-
-        ui.horizontal(|ui| {
-            ui.label("Seconds:");
-            ui.add(egui::DragValue::new(&mut secs).range(0..=59));
-        });
-        ui.horizontal(|ui| {
-            ui.label("Ticks:");
-            ui.add(egui::DragValue::new(&mut ticks).range(0..=40));
-        });
-
-        if secs != self.secs || ticks != self.ticks {
-            app_state.push_undo();
-            self.secs = secs;
-            self.ticks = ticks;
-        }
-
-        let total_ticks = secs as u32 * 40 + ticks as u32;
-        let total_secs = secs as f64 + ticks as f64 * 0.025;
-        ui.label(format!("Total: {:.3}s ({}t)", total_secs, total_ticks));
+fn timer_properties(timer: &mut Timer, ui: &mut Ui, app_state: &mut AppState) {
+    let mut secs = timer.secs;
+    let mut ticks = timer.ticks;
+
+    ui.horizontal(|ui| {
+        ui.label("Seconds:");
+        ui.add(egui::DragValue::new(&mut secs).range(0..=59));
+    });
+    ui.horizontal(|ui| {
+        ui.label("Ticks:");
+        ui.add(egui::DragValue::new(&mut ticks).range(0..=40));
+    });
+
+    if secs != timer.secs || ticks != timer.ticks {
+        app_state.push_undo();
+        timer.secs = secs;
+        timer.ticks = ticks;
     }
+
+    let total_ticks = secs as u32 * 40 + ticks as u32;
+    let total_secs = secs as f64 + ticks as f64 * 0.025;
+    ui.label(format!("Total: {:.3}s ({}t)", total_secs, total_ticks));
+    ui.checkbox(&mut timer.active, "Starts on")
+        .on_hover_text(STARTS_ON_HELP);
 }
 
-impl Module {
-    pub fn draw(&self, part: &Part, painter: &Painter, app_state: &AppState) {
+impl DrawPart for Module {
+    fn draw(&self, part: &Part, painter: &Painter, app_state: &AppState) {
         // skip rendering if off-screen
         let screen_rect = Rect::from_min_max(
             app_state.world_to_screen(part.pos),
@@ -496,45 +513,44 @@ impl Module {
             }
         }
     }
-    pub fn draw_properties(&mut self, ui: &mut Ui, app_state: &mut AppState) {
-        ui.label(format!(
-            "File Path: {}",
-            path_to_string(self.path.clone(), app_state.project_folder.clone())
-        ))
-        .on_hover_text(self.path.to_string_lossy());
-        if ui.button("Change File").clicked() {
-            app_state.push_undo();
-            let file = rfd::FileDialog::new()
-                .add_filter("SM Logic", &["sml"])
-                .pick_file();
-            if let Some(path) = file {
-                self.path = if let Some(project_folder) = &app_state.project_folder {
-                    path.strip_prefix(project_folder)
-                        .map(|p| p.to_path_buf())
-                        .unwrap_or(path)
-                } else {
-                    path
-                };
-            }
-            self.reload(
-                app_state.project_folder.clone(),
-                &mut app_state.toasts,
-                Vec::new(),
-            );
-        }
-        if ui.button("Reload File").clicked() {
-            app_state.push_undo();
-            self.reload(
-                app_state.project_folder.clone(),
-                &mut app_state.toasts,
-                Vec::new(),
-            );
+}
+
+fn reload_module(module: &mut Module, app_state: &mut AppState) {
+    let mut errors = Vec::new();
+    module.reload(app_state.project_folder.clone(), &mut errors, Vec::new());
+    app_state.report_module_load(module.loaded_modules(), errors);
+}
+
+fn module_properties(module: &mut Module, ui: &mut Ui, app_state: &mut AppState) {
+    ui.label(format!(
+        "File Path: {}",
+        path_to_string(module.path.clone(), app_state.project_folder.clone())
+    ))
+    .on_hover_text(module.path.to_string_lossy());
+    if ui.button("Change File").clicked() {
+        app_state.push_undo();
+        let file = rfd::FileDialog::new()
+            .add_filter("SM Logic", &["sml"])
+            .pick_file();
+        if let Some(path) = file {
+            module.path = if let Some(project_folder) = &app_state.project_folder {
+                path.strip_prefix(project_folder)
+                    .map(|p| p.to_path_buf())
+                    .unwrap_or(path)
+            } else {
+                path
+            };
         }
+        reload_module(module, app_state);
+    }
+    if ui.button("Reload File").clicked() {
+        app_state.push_undo();
+        reload_module(module, app_state);
     }
 }
 
-impl IO {
-    pub fn draw(&self, part: &Part, painter: &Painter, app_state: &AppState) {
+impl DrawPart for IO {
+    fn draw(&self, part: &Part, painter: &Painter, app_state: &AppState) {
         // skip rendering if off-screen
         let screen_rect = Rect::from_min_max(
             app_state.world_to_screen(part.pos),
@@ -632,8 +648,8 @@ impl IO {
     }
 }
 
-impl Label {
-    pub fn draw(&self, part: &Part, painter: &Painter, app_state: &AppState) {
+impl DrawPart for Label {
+    fn draw(&self, part: &Part, painter: &Painter, app_state: &AppState) {
         // skip rendering if off-screen
         let screen_rect = Rect::from_min_max(
             app_state.world_to_screen(part.pos),
@@ -663,12 +679,12 @@ impl Label {
     }
 }
 
-impl Part {
-    pub fn draw_properties(&mut self, ui: &mut Ui, app_state: &mut AppState) {
+impl PartProperties for Part {
+    fn draw_properties(&mut self, ui: &mut Ui, app_state: &mut AppState) {
         match &mut self.part_data {
-            PartData::Gate(gate) => gate.draw_properties(ui, app_state, &mut self.label),
-            PartData::Timer(timer) => timer.draw_properties(ui, app_state),
-            PartData::Module(module) => module.draw_properties(ui, app_state),
+            PartData::Gate(gate) => gate_properties(gate, ui, app_state, &mut self.label),
+            PartData::Timer(timer) => timer_properties(timer, ui, app_state),
+            PartData::Module(module) => module_properties(module, ui, app_state),
             _ => {}
         }
         ui.horizontal(|ui| {
@@ -713,7 +729,7 @@ impl Part {
 impl AppState {
     pub fn draw_selected_connections(&self, connector_data: ConnectorData, painter: &Painter) {
         for port in connector_data.selected_ports {
-            if let Some(pos) = port.pos(self) {
+            if let Some(pos) = port.pos(&self.canvas_snapshot) {
                 painter.circle_stroke(
                     self.world_to_screen(pos),
                     10.0 * self.zoom,
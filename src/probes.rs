@@ -0,0 +1,110 @@
+use crate::parts::{PartData, sort_by_position};
+use crate::simulator::ImportantGate;
+use crate::state::{AppState, CanvasSnapshot};
+use egui::Ui;
+use std::collections::HashMap;
+
+/// one level of the probe tree. `path` and `ancestors` are the module ids and labels above it
+fn probe_tree(
+    ui: &mut Ui,
+    canvas: &CanvasSnapshot,
+    path: &[u64],
+    ancestors: &[String],
+    indexes: &HashMap<Vec<u64>, usize>,
+    probes: &[ImportantGate],
+    toggled: &mut Option<ImportantGate>,
+) {
+    let mut parts: Vec<_> = canvas.parts.values().collect();
+    sort_by_position(&mut parts, |p| p.pos);
+    for part in parts {
+        let mut part_path = path.to_vec();
+        part_path.push(part.id);
+        let type_label = match &part.part_data {
+            PartData::Module(module) => {
+                let mut sub_ancestors = ancestors.to_vec();
+                sub_ancestors.push(part.label.clone());
+                egui::CollapsingHeader::new(&part.label)
+                    .id_salt(&part_path)
+                    .show(ui, |ui| {
+                        probe_tree(
+                            ui,
+                            &module.canvas_snapshot,
+                            &part_path,
+                            &sub_ancestors,
+                            indexes,
+                            probes,
+                            toggled,
+                        );
+                    });
+                continue;
+            }
+            // the top level is already on the canvas, only modules are worth opening
+            _ if path.is_empty() => continue,
+            PartData::Gate(gate) => gate.gate_type.to_label(),
+            PartData::Timer(_) => "Timer".to_string(),
+            PartData::IO(io) if io.input => "Input".to_string(),
+            PartData::IO(_) => "Output".to_string(),
+            PartData::Label(_) => continue,
+        };
+        let tree = ancestors.join(" > ");
+        let text = format!("{} ({})", part.label, type_label);
+        let Some(&index) = indexes.get(&part_path) else {
+            ui.add_enabled(false, egui::Label::new(text))
+                .on_disabled_hover_text("Not connected to anything that's simulated");
+            continue;
+        };
+        let mut probed = probes
+            .iter()
+            .any(|g| g.simulation_index == index && g.tree == tree);
+        if ui.checkbox(&mut probed, text).changed() {
+            *toggled = Some(ImportantGate {
+                label: part.label.clone(),
+                color: part.color,
+                tree,
+                simulation_index: index,
+                gate_type_label: type_label,
+            });
+        }
+    }
+}
+
+impl AppState {
+    /// tree of every module instance, ticking a part inside adds it to the important sidebar
+    /// (and the waveform panel) without having to mark it important in the module file
+    pub fn draw_probes(&mut self, ui: &mut Ui) {
+        if self.sim_state.is_none() {
+            return;
+        }
+        ui.separator();
+        let mut toggled = None;
+        egui::CollapsingHeader::new("Probes")
+            .id_salt("probes")
+            .show(ui, |ui| {
+                egui::ScrollArea::vertical()
+                    .id_salt("probe_tree")
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        probe_tree(
+                            ui,
+                            &self.canvas_snapshot,
+                            &[],
+                            &[],
+                            &self.probe_indexes,
+                            &self.important_gates,
+                            &mut toggled,
+                        );
+                    });
+            });
+        if let Some(probe) = toggled {
+            if let Some(i) = self
+                .important_gates
+                .iter()
+                .position(|g| g.simulation_index == probe.simulation_index && g.tree == probe.tree)
+            {
+                self.important_gates.remove(i);
+            } else {
+                self.important_gates.push(probe);
+            }
+        }
+    }
+}
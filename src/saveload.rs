@@ -2,10 +2,9 @@ use crate::AppState;
 use crate::colors::ColorPallet;
 use crate::connections::Connection;
 use crate::exporter::{ExporterSettings, get_bp_folder};
-use crate::parts::{Part, PartData, Port};
-use crate::state::{CanvasSnapshot, Selection};
+use crate::parts::{Part, Port};
+use crate::state::Selection;
 use egui::{Key, Pos2};
-use egui_notify::Toasts;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
@@ -119,49 +118,13 @@ impl Default for Config {
                 ("exporter".to_string(), Some(Key::Num4)),
                 ("simulator pause".to_string(), Some(Key::Space)),
                 ("simulator tick".to_string(), Some(Key::Tab)),
+                ("simulator step back".to_string(), Some(Key::B)),
                 ("rename".to_string(), Some(Key::Backtick)),
             ]),
         }
     }
 }
 
-// saving/loading modules
-impl CanvasSnapshot {
-    pub fn save(&self, path: PathBuf) -> Result<(), Box<dyn std::error::Error>> {
-        let json = serde_json::to_value(self)?;
-        let pretty = serde_json::to_string_pretty(&json)?;
-        std::fs::write(path, pretty)?;
-        Ok(())
-    }
-
-    pub fn load(
-        path: PathBuf,
-        project_path: Option<PathBuf>,
-        toasts: &mut Toasts,
-        ancestors: Vec<PathBuf>,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
-        let contents = std::fs::read_to_string(path)?;
-        let json: serde_json::Value = serde_json::from_str(&contents)?;
-
-        let mut canvas_snapshot: Self = serde_json::from_value(json)?;
-        canvas_snapshot.reload_modules(project_path, toasts, ancestors);
-        Ok(canvas_snapshot)
-    }
-
-    pub fn reload_modules(
-        &mut self,
-        project_path: Option<PathBuf>,
-        toasts: &mut Toasts,
-        ancestors: Vec<PathBuf>,
-    ) {
-        for part in self.parts.values_mut() {
-            if let PartData::Module(data) = &mut part.part_data {
-                data.reload(project_path.clone(), toasts, ancestors.clone());
-            }
-        }
-    }
-}
-
 #[derive(Serialize, Deserialize, Clone)]
 pub struct ClipboardData {
     parts: Vec<Part>,
@@ -1,51 +1,30 @@
+use crate::breakpoints::BreakpointInputs;
+use crate::buses::BusInputs;
 use crate::colors::{ColorPallet, DEFAULT_GATE_COLOR};
-use crate::connections::{Connection, WIRE_WIDTH, compute_wire_route, dist_point_to_segment};
+use crate::connections::{WIRE_WIDTH, compute_wire_route, dist_point_to_segment};
 use crate::egui::{Color32, Pos2, Rect, Vec2};
+use crate::exporter::ExportPreviewInputs;
+use crate::importer::ImportInputs;
 use crate::lua_scripting::LuaScript;
+use crate::memory::MemoryInputs;
+use crate::module_view::ModuleView;
 use crate::parts::{PORT_SIZE, Part, Port};
 use crate::saveload::{ClipboardData, Config};
 use crate::simulator::{ImportantGate, SimSnapshot, SimState};
-use crate::tools::Tool;
+use crate::tools::{SimClickMode, Tool};
+use crate::waveform::WaveformView;
 use egui_notify::Toasts;
 use parking_lot::Mutex;
-use serde::{Deserialize, Serialize};
+use smlogic_core::breakpoints::Breakpoint;
+use smlogic_core::testing::TestResult;
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Instant;
 
-const MAX_PATH_LEN: usize = 20;
-const RESIZE_HITBOX: Vec2 = Vec2::new(-12.0, -12.0);
+pub use smlogic_core::canvas::{CanvasSnapshot, path_to_string};
 
-pub fn path_to_string(path: PathBuf, project_folder: Option<PathBuf>) -> String {
-    let out = if let Some(folder) = project_folder {
-        path.strip_prefix(folder)
-            .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_else(|_| path.to_string_lossy().to_string())
-    } else {
-        path.to_string_lossy().to_string()
-    };
-
-    let file_name_len = path
-        .file_name()
-        .map(|n| n.to_string_lossy().chars().count())
-        .unwrap_or(0);
-    let max_len = MAX_PATH_LEN.max(file_name_len);
-    if out.chars().count() <= max_len {
-        out
-    } else {
-        format!(
-            "...{}",
-            out.chars()
-                .rev()
-                .take(max_len)
-                .collect::<String>()
-                .chars()
-                .rev()
-                .collect::<String>()
-        )
-    }
-}
+const RESIZE_HITBOX: Vec2 = Vec2::new(-12.0, -12.0);
 
 //operation being completed, ie box selecting, resizing, etc
 #[derive(Clone, PartialEq)]
@@ -70,13 +49,6 @@ impl Default for InteractionState {
     }
 }
 
-#[derive(Clone, Deserialize, Serialize, PartialEq)]
-pub struct CanvasSnapshot {
-    pub parts: HashMap<u64, Part>,
-    pub connections: Vec<Connection>,
-    pub next_id: u64,
-}
-
 pub struct AppState {
     pub config: Config,
     pub interaction_state: InteractionState,
@@ -98,6 +70,7 @@ pub struct AppState {
     // other live info
     pub pan_offset: Vec2,
     pub zoom: f32,
+    pub canvas_rect: Rect, // where the canvas was drawn last frame
     pub canvas_snapshot: CanvasSnapshot,
     pub selection: Vec<Selection>,
     pub last_project_reload: Instant,
@@ -105,14 +78,27 @@ pub struct AppState {
     pub lua_script: Option<LuaScript>,
     pub rebinding: Option<String>,
     pub hide_connections: bool,
+    pub test_results: Option<Vec<TestResult>>,
     // simulation info
     pub sim_snapshot: Option<Arc<Mutex<SimSnapshot>>>,
     pub sim_state_outputs_snapshot: Option<Vec<bool>>,
     pub sim_state: Option<Arc<Mutex<SimState>>>,
     pub important_gates: Vec<ImportantGate>,
+    pub probe_indexes: HashMap<Vec<u64>, usize>, // part path -> simulation index, for probes
+    pub module_view: Option<ModuleView>,
+    pub module_view_request: Option<Vec<u64>>, // module to open once the canvas is drawn
     pub last_tick_count: u64,
     pub last_tps_check: Instant,
     pub current_tps: f64,
+    pub sim_click_mode: SimClickMode,
+    pub waveform_open: bool,
+    pub waveform_view: WaveformView,
+    pub breakpoint_inputs: BreakpointInputs,
+    pub breakpoint_hit: Option<Breakpoint>,
+    pub bus_inputs: BusInputs,
+    pub memory_inputs: MemoryInputs,
+    pub import_inputs: ImportInputs,
+    pub export_preview: ExportPreviewInputs,
     // settings
     pub show_grid: bool,
     pub show_connection_count: bool,
@@ -145,11 +131,8 @@ impl AppState {
             current_module_path: None,
             pan_offset: Vec2::ZERO,
             zoom: 1.0,
-            canvas_snapshot: CanvasSnapshot {
-                parts: HashMap::new(),
-                connections: Vec::new(),
-                next_id: 0,
-            },
+            canvas_rect: Rect::NOTHING,
+            canvas_snapshot: CanvasSnapshot::new(),
             undo_stack: Vec::new(),
             redo_stack: Vec::new(),
             connection_counts: HashMap::new(),
@@ -161,13 +144,26 @@ impl AppState {
             lua_script: None,
             rebinding: None,
             hide_connections: false,
+            test_results: None,
             sim_snapshot: None,
             sim_state_outputs_snapshot: None,
             sim_state: None,
             important_gates: Vec::new(),
+            probe_indexes: HashMap::new(),
+            module_view: None,
+            module_view_request: None,
             last_tick_count: 0,
             last_tps_check: Instant::now(),
             current_tps: 0.0,
+            sim_click_mode: SimClickMode::Toggle,
+            waveform_open: false,
+            waveform_view: WaveformView::NEW,
+            breakpoint_inputs: BreakpointInputs::NEW,
+            breakpoint_hit: None,
+            bus_inputs: BusInputs::NEW,
+            memory_inputs: MemoryInputs::NEW,
+            import_inputs: ImportInputs::NEW,
+            export_preview: ExportPreviewInputs::NEW,
             show_grid: config.show_grid,
             snap_to_grid: config.snap_to_grid,
             show_connection_count: config.show_connection_count,
@@ -231,8 +227,10 @@ impl AppState {
         let half_width = WIRE_WIDTH * 0.5;
 
         for (i, connection) in self.canvas_snapshot.connections.iter().enumerate() {
-            if let (Some(start), Some(end)) = (connection.start.pos(self), connection.end.pos(self))
-            {
+            if let (Some(start), Some(end)) = (
+                connection.start.pos(&self.canvas_snapshot),
+                connection.end.pos(&self.canvas_snapshot),
+            ) {
                 let points = compute_wire_route(start, end);
 
                 for seg in points.windows(2) {
@@ -254,9 +252,10 @@ impl AppState {
             .iter()
             .enumerate()
             .filter(|(_i, connection)| {
-                if let (Some(start), Some(end)) =
-                    (connection.start.pos(self), connection.end.pos(self))
-                {
+                if let (Some(start), Some(end)) = (
+                    connection.start.pos(&self.canvas_snapshot),
+                    connection.end.pos(&self.canvas_snapshot),
+                ) {
                     let points = compute_wire_route(start, end);
 
                     points.windows(2).any(|seg| {
@@ -379,12 +378,18 @@ impl AppState {
         }
     }
     pub fn open_file(&mut self, path: PathBuf) {
+        let mut errors = Vec::new();
         let new_snapshot = CanvasSnapshot::load(
             path.clone(),
             self.project_folder.clone(),
-            &mut self.toasts,
+            &mut errors,
             Vec::new(),
         );
+        let loaded = match &new_snapshot {
+            Ok(snapshot) => snapshot.loaded_modules(),
+            Err(_) => Vec::new(),
+        };
+        self.report_module_load(loaded, errors);
         match new_snapshot {
             Ok(snapshot) => {
                 self.canvas_snapshot = snapshot;
@@ -0,0 +1,85 @@
+use crate::parts::{PartData, sort_by_position};
+use crate::state::AppState;
+use egui::{DragValue, Ui};
+use smlogic_core::stimulus::Stimulus;
+
+impl AppState {
+    /// clock, pulse and noise sources for the top level inputs
+    pub fn draw_stimuli(&mut self, ui: &mut Ui) {
+        let Some(sim_state) = self.sim_state.clone() else {
+            return;
+        };
+        let mut inputs: Vec<_> = self
+            .canvas_snapshot
+            .parts
+            .values()
+            .filter(|p| matches!(&p.part_data, PartData::IO(io) if io.input))
+            .filter_map(|p| Some((p.label.clone(), p.simulation_index?, p.pos)))
+            .collect();
+        if inputs.is_empty() {
+            return;
+        }
+        sort_by_position(&mut inputs, |(_, _, pos)| *pos);
+        ui.separator();
+        egui::CollapsingHeader::new("Stimulus")
+            .id_salt("stimulus")
+            .show(ui, |ui| {
+                let mut state = sim_state.lock();
+                let tick = state.tick;
+                for (label, index, _) in inputs {
+                    let position = state.stimuli.iter().position(|(i, _)| *i == index);
+                    let mut stimulus = position.map(|p| state.stimuli[p].1);
+                    let before = stimulus;
+                    ui.horizontal(|ui| {
+                        ui.label(&label);
+                        egui::ComboBox::from_id_salt(("stimulus", index))
+                            .width(60.0)
+                            .selected_text(stimulus.map_or("None", |s| s.to_label()))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut stimulus, None, "None");
+                                for kind in Stimulus::KINDS {
+                                    let selected = stimulus.is_some_and(|s| {
+                                        std::mem::discriminant(&s) == std::mem::discriminant(kind)
+                                    });
+                                    if ui.selectable_label(selected, kind.to_label()).clicked()
+                                        && !selected
+                                    {
+                                        stimulus = Some(match *kind {
+                                            // a pulse nobody will see is pointless, start it now
+                                            Stimulus::Pulse { length, .. } => Stimulus::Pulse {
+                                                start: tick,
+                                                length,
+                                            },
+                                            kind => kind,
+                                        });
+                                    }
+                                }
+                            });
+                    });
+                    if let Some(stimulus) = &mut stimulus {
+                        ui.horizontal(|ui| match stimulus {
+                            Stimulus::Clock { period, duty } => {
+                                ui.label("period");
+                                ui.add(DragValue::new(period).range(1..=u64::MAX));
+                                ui.label("on for");
+                                ui.add(DragValue::new(duty).range(0..=*period));
+                            }
+                            Stimulus::Pulse { start, length } => {
+                                ui.label("at tick");
+                                ui.add(DragValue::new(start));
+                                ui.label("for");
+                                ui.add(DragValue::new(length).range(1..=u64::MAX));
+                            }
+                            Stimulus::Noise { seed } => {
+                                ui.label("seed");
+                                ui.add(DragValue::new(seed));
+                            }
+                        });
+                    }
+                    if stimulus != before {
+                        state.set_stimulus(index, stimulus);
+                    }
+                }
+            });
+    }
+}
@@ -0,0 +1,83 @@
+use crate::state::{AppState, path_to_string};
+use smlogic_core::testing::{parse_test_file, run_tests, test_file_path};
+
+impl AppState {
+    /// runs the `.sml.test` file next to the open module against whats on the canvas right now
+    pub fn run_module_tests(&mut self) {
+        let Some(module_path) = self.current_module_path.clone() else {
+            self.toasts.error("Save the module first to run its tests!");
+            return;
+        };
+        let test_path = test_file_path(&module_path);
+        let contents = match std::fs::read_to_string(&test_path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                self.toasts.error(format!(
+                    "Failed to read {}: {}",
+                    path_to_string(test_path, self.project_folder.clone()),
+                    e
+                ));
+                return;
+            }
+        };
+        let cases = match parse_test_file(&contents) {
+            Ok(cases) => cases,
+            Err(e) => {
+                self.toasts.error(format!("Invalid test file: {}", e));
+                return;
+            }
+        };
+
+        let results = run_tests(&self.canvas_snapshot, &cases);
+        let failed = results.iter().filter(|r| !r.passed()).count();
+        if failed == 0 {
+            self.toasts
+                .success(format!("All {} tests passed!", results.len()));
+        } else {
+            self.toasts
+                .error(format!("{} of {} tests failed", failed, results.len()));
+        }
+        self.test_results = Some(results);
+    }
+
+    pub fn draw_test_results(&mut self, ctx: &egui::Context) {
+        let Some(results) = &self.test_results else {
+            return;
+        };
+        let mut open = true;
+        egui::Window::new("Test Results")
+            .open(&mut open)
+            .default_width(300.0)
+            .resizable(true)
+            .frame(
+                egui::Frame::new()
+                    .fill(self.color_pallet.base)
+                    .inner_margin(8.0),
+            )
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for result in results {
+                        let name = if result.name.is_empty() {
+                            "(unnamed)"
+                        } else {
+                            &result.name
+                        };
+                        if result.passed() {
+                            ui.colored_label(egui::Color32::GREEN, format!("passed: {}", name));
+                        } else {
+                            ui.colored_label(egui::Color32::RED, format!("failed: {}", name));
+                            if let Some(error) = &result.error {
+                                ui.label(format!("    {}", error));
+                            }
+                            for failure in &result.failures {
+                                ui.label(format!("    {}", failure));
+                            }
+                        }
+                    }
+                });
+            });
+        if !open {
+            self.test_results = None;
+        }
+    }
+}
@@ -1,10 +1,13 @@
 use crate::colors::SM_PALETTE;
 use crate::connections::Connection;
-use crate::exporter::{ExportType, ExporterSettings};
-use crate::parts::{Part, PartType, Port};
+use crate::exporter::{ExportType, ExporterSettings, Placement};
+use crate::parts::{PartType, Port, sort_by_position};
 use crate::state::{AppState, Selection};
 use eframe::egui::Pos2;
 use egui::{Stroke, Ui, Vec2};
+use smlogic_core::compiled::Engine;
+use smlogic_core::history::DEFAULT_REWIND;
+use smlogic_core::semantics::ForceMode;
 use std::path::PathBuf;
 use std::time::{Duration, Instant};
 
@@ -157,6 +160,55 @@ impl ConnectorData {
     }
 }
 
+const REWIND_HISTORY_HELP: &str = "Lets the simulation be stepped back, saving every tick \
+makes it a lot slower so its off by default";
+
+const FORCE_MODE_HELP: &str = "Which parts can be toggled or written to by buses and memory. \
+Switches only skips anything with inputs. neither changes how the gates tick";
+
+/// shown when clicking a driven part with switches only on
+const NOT_FORCEABLE: &str = "Only parts with no inputs can be toggled in Switches only mode";
+
+const EXPORT_STATE_HELP: &str = "Exports with the last exporter settings, every gate starts \
+the way it is right now";
+
+const KEEP_POSITIONS_HELP: &str = "Parts that were exported from this module to this blueprint \
+before go back where they were, so re-exporting doesnt break anything built around it. Placement \
+and the size limits only apply to new parts while this is on";
+
+const PLACEMENT_HELP: &str = "What order gates fill up the block in. Raster goes in canvas order, \
+By module keeps every module together and By depth orders gates by how far they are from an \
+input, so wires mostly go one way. Canvas layout puts every part where it is on the canvas \
+(scaled by the IO scales) and ignores the size limits. Parts kept from the last export dont move, \
+turn off Keep positions to lay everything out again";
+
+const PREVIEW_HELP: &str = "Shows where everything would go in game without exporting, updates as \
+you change the settings";
+
+const THREADS_HELP: &str = "Splits each tick over more threads, only for the Simple engine. \
+every thread needs at least 4,000 parts, so smaller circuits use fewer than you set";
+
+/// what clicking on a part does while simulating
+#[derive(Clone, PartialEq)]
+pub enum SimClickMode {
+    Toggle,
+    Record,
+    Breakpoint,
+    BusBits,
+}
+impl SimClickMode {
+    pub const MODES: &[Self] = &[Self::Toggle, Self::Record, Self::Breakpoint, Self::BusBits];
+
+    pub fn to_label(&self) -> &'static str {
+        match self {
+            SimClickMode::Toggle => "Toggle",
+            SimClickMode::Record => "Record waveform",
+            SimClickMode::Breakpoint => "Breakpoint",
+            SimClickMode::BusBits => "Pick bus bits",
+        }
+    }
+}
+
 //current tool being used
 #[derive(Clone, PartialEq)]
 pub enum Tool {
@@ -181,6 +233,7 @@ impl AppState {
     pub fn draw_sidebar_tool_properties(&mut self, ui: &mut Ui) {
         let mut connect = false;
         let mut export = false;
+        let mut preview = false;
         let exporter_before = if let Some(Tool::Exporter(s)) = &self.active_tool {
             Some(s.clone())
         } else {
@@ -305,19 +358,29 @@ impl AppState {
                 ui.separator();
                 ui.heading("Simulator");
                 if let Some(sim_snapshot) = &self.sim_snapshot {
-                    let (running, tick, target_spt, part_count) = {
+                    let (running, tick, target_spt, part_count, oldest_saved_tick) = {
                         let snapshot = sim_snapshot.lock();
                         self.sim_state_outputs_snapshot = Some(snapshot.outputs.clone());
+                        if let Some(breakpoint) = &snapshot.breakpoint_hit
+                            && snapshot.breakpoint_hit != self.breakpoint_hit
+                        {
+                            self.toasts
+                                .info(format!("Breakpoint hit: {}", breakpoint.to_label()));
+                        }
+                        self.breakpoint_hit = snapshot.breakpoint_hit.clone();
                         (
                             snapshot.running,
                             snapshot.tick,
                             snapshot.target_spt,
                             snapshot.outputs.len(),
+                            snapshot.oldest_saved_tick,
                         )
                     };
 
                     let mut new_running = running;
                     let mut new_step = false;
+                    let mut step_back = false;
+                    let mut rewind_to = None;
                     let mut new_target_spt = target_spt;
                     let mut mutations = false;
 
@@ -326,9 +389,36 @@ impl AppState {
                         mutations = true;
                     }
                     if !running {
-                        if ui.button("Tick Step").clicked() {
-                            new_step = true;
-                            mutations = true;
+                        ui.horizontal(|ui| {
+                            if ui
+                                .add_enabled(
+                                    oldest_saved_tick.is_some(),
+                                    egui::Button::new("Step Back"),
+                                )
+                                .clicked()
+                            {
+                                step_back = true;
+                                mutations = true;
+                            }
+                            if ui.button("Tick Step").clicked() {
+                                new_step = true;
+                                mutations = true;
+                            }
+                        });
+                        if let Some(oldest) = oldest_saved_tick
+                            && oldest < tick
+                        {
+                            let mut rewind_tick = tick;
+                            ui.horizontal(|ui| {
+                                ui.label("Rewind:");
+                                if ui
+                                    .add(egui::Slider::new(&mut rewind_tick, oldest..=tick))
+                                    .changed()
+                                {
+                                    rewind_to = Some(rewind_tick);
+                                    mutations = true;
+                                }
+                            });
                         }
                     }
                     let mut limit_tps = target_spt.is_some();
@@ -365,18 +455,131 @@ impl AppState {
                         format_with_commas(self.current_tps as u64)
                     ));
                     ui.label(format!("Parts: {}", part_count));
-
-                    // lock variables again if something has to be changed
-                    if mutations {
-                        if let Some(sim_state) = &self.sim_state {
-                            let mut state = sim_state.lock();
+                    if let Some(sim_state) = &self.sim_state {
+                        // one lock for all the settings, so the sim thread isnt stopped for each
+                        let mut state = sim_state.lock();
+                        if mutations {
                             state.running = new_running;
                             if new_step {
                                 state.step = true;
                             }
+                            if step_back {
+                                state.step_back();
+                            }
+                            if let Some(tick) = rewind_to {
+                                state.rewind_to(tick);
+                            }
                             state.target_spt = new_target_spt;
                         }
+                        ui.horizontal(|ui| {
+                            ui.label("Engine:");
+                            egui::ComboBox::from_id_salt("sim_engine_combo")
+                                .selected_text(state.engine.to_label())
+                                .show_ui(ui, |ui| {
+                                    for engine in Engine::ENGINES {
+                                        ui.selectable_value(
+                                            &mut state.engine,
+                                            *engine,
+                                            engine.to_label(),
+                                        );
+                                    }
+                                });
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Forcing:");
+                            egui::ComboBox::from_id_salt("sim_force_mode_combo")
+                                .selected_text(state.force_mode.to_label())
+                                .show_ui(ui, |ui| {
+                                    for mode in ForceMode::MODES {
+                                        ui.selectable_value(
+                                            &mut state.force_mode,
+                                            *mode,
+                                            mode.to_label(),
+                                        );
+                                    }
+                                })
+                                .response
+                                .on_hover_text(FORCE_MODE_HELP);
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Threads:");
+                            let max_threads = std::thread::available_parallelism()
+                                .map(|n| n.get())
+                                .unwrap_or(1);
+                            let simple = state.engine == Engine::Simple;
+                            ui.add_enabled(
+                                simple,
+                                egui::DragValue::new(&mut state.threads).range(1..=max_threads),
+                            )
+                            .on_hover_text(THREADS_HELP);
+                        });
+                        ui.horizontal(|ui| {
+                            let mut rewind = state.history.capacity > 0;
+                            if ui
+                                .checkbox(&mut rewind, "Rewind history")
+                                .on_hover_text(REWIND_HISTORY_HELP)
+                                .changed()
+                            {
+                                state
+                                    .history
+                                    .set_capacity(if rewind { DEFAULT_REWIND } else { 0 });
+                            }
+                            let mut capacity = state.history.capacity;
+                            if rewind
+                                && ui
+                                    .add(
+                                        egui::DragValue::new(&mut capacity)
+                                            .range(1..=100_000)
+                                            .suffix(" ticks"),
+                                    )
+                                    .changed()
+                            {
+                                state.history.set_capacity(capacity);
+                            }
+                        });
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.button("Save state").clicked() {
+                            self.save_sim_state();
+                        }
+                        if ui.button("Load state").clicked() {
+                            self.load_sim_state();
+                        }
+                    });
+                    if ui
+                        .button("Export with current state")
+                        .on_hover_text(EXPORT_STATE_HELP)
+                        .clicked()
+                    {
+                        self.export_current_state();
                     }
+
+                    ui.horizontal(|ui| {
+                        ui.label("Click: ");
+                        egui::ComboBox::from_id_salt("sim_click_combo")
+                            .width(10.0)
+                            .selected_text(self.sim_click_mode.to_label())
+                            .show_ui(ui, |ui| {
+                                for mode in SimClickMode::MODES {
+                                    if ui
+                                        .selectable_label(
+                                            &self.sim_click_mode == mode,
+                                            mode.to_label(),
+                                        )
+                                        .clicked()
+                                    {
+                                        self.sim_click_mode = mode.clone();
+                                    }
+                                }
+                            })
+                    });
+                    ui.checkbox(&mut self.waveform_open, "Show waveforms");
+                    self.draw_stimuli(ui);
+                    self.draw_buses(ui);
+                    self.draw_memories(ui);
+                    self.draw_breakpoints(ui);
+                    self.draw_oscillation(ui);
+                    self.draw_probes(ui);
                 } else {
                     self.active_tool = None;
                 }
@@ -388,7 +591,7 @@ impl AppState {
                     .on_hover_text(
                         "Places the IO and Important parts as they are positioned in the canvas",
                     );
-                if settings.maintain_io_position {
+                if settings.maintain_io_position || settings.placement == Placement::Canvas {
                     ui.horizontal(|ui| {
                         ui.label("IO X scale: ");
                         ui.add(egui::DragValue::new(&mut settings.io_x_scale).fixed_decimals(6))
@@ -398,6 +601,24 @@ impl AppState {
                         ui.add(egui::DragValue::new(&mut settings.io_y_scale).fixed_decimals(6))
                     });
                 }
+                ui.checkbox(&mut settings.keep_positions, "Keep positions")
+                    .on_hover_text(KEEP_POSITIONS_HELP);
+                ui.horizontal(|ui| {
+                    ui.label("Placement:");
+                    egui::ComboBox::from_id_salt("exporter_placement_combo")
+                        .selected_text(settings.placement.to_label())
+                        .show_ui(ui, |ui| {
+                            for placement in Placement::PLACEMENTS {
+                                ui.selectable_value(
+                                    &mut settings.placement,
+                                    placement.clone(),
+                                    placement.to_label(),
+                                );
+                            }
+                        })
+                        .response
+                        .on_hover_text(PLACEMENT_HELP);
+                });
                 let mut limit_x = settings.max_x.is_some();
                 if ui.checkbox(&mut limit_x, "Limit X size").changed() {
                     if limit_x {
@@ -580,9 +801,14 @@ impl AppState {
                     });
                 }
 
-                if ui.button("Export!").clicked() {
-                    export = true;
-                }
+                ui.horizontal(|ui| {
+                    if ui.button("Export!").clicked() {
+                        export = true;
+                    }
+                    if ui.button("Preview").on_hover_text(PREVIEW_HELP).clicked() {
+                        preview = true;
+                    }
+                });
             }
             _ => {}
         }
@@ -612,8 +838,14 @@ impl AppState {
             if before != *after {
                 self.config.export_settings = after.clone();
                 self.config.save();
+                if self.export_preview.open {
+                    self.refresh_export_preview();
+                }
             }
         }
+        if preview {
+            self.open_export_preview();
+        }
     }
 }
 
@@ -633,7 +865,7 @@ impl AppState {
         match self.active_tool.clone() {
             Some(Tool::PlacePart(part_type)) => {
                 self.push_undo();
-                let part_id = Part::new(part_type, self, world_pos);
+                let part_id = self.add_part(part_type, world_pos);
                 self.reload_connection_counts();
                 self.select_part(part_id, shift_held);
             }
@@ -648,26 +880,42 @@ impl AppState {
                 self.selection.clear();
             }
             Some(Tool::Simulator) => {
-                if let Some(connection_index) = self.connection_at_pos(world_pos) {
-                    if let Some(connection) = self.canvas_snapshot.connections.get(connection_index)
-                    {
-                        if let Some(new_i) = connection.simulation_index {
+                // simulation index and name of whatever was clicked, connections go by the part
+                // driving them
+                let clicked = if let Some(connection_index) = self.connection_at_pos(world_pos) {
+                    self.canvas_snapshot
+                        .connections
+                        .get(connection_index)
+                        .and_then(|connection| {
+                            let label = self
+                                .canvas_snapshot
+                                .parts
+                                .get(&connection.start.part)
+                                .map(|p| p.label.clone())
+                                .unwrap_or_default();
+                            Some((connection.simulation_index?, label))
+                        })
+                } else if let Some(part) = self.part_at_pos(world_pos) {
+                    part.simulation_index.map(|i| (i, part.label.clone()))
+                } else {
+                    None
+                };
+                if let Some((new_i, label)) = clicked {
+                    match self.sim_click_mode {
+                        SimClickMode::Toggle => {
                             if let Some(sim_state) = &self.sim_state {
                                 let mut state = sim_state.lock();
-                                let new_val = !state.part_outputs[new_i];
-                                state.part_outputs[new_i] = new_val;
-                                state.prev_outputs[new_i] = new_val;
+                                if state.can_force(new_i) {
+                                    let new_val = !state.part_outputs[new_i];
+                                    state.set_output(new_i, new_val);
+                                } else {
+                                    self.toasts.error(NOT_FORCEABLE);
+                                }
                             }
                         }
-                    }
-                } else if let Some(part) = self.part_at_pos(world_pos) {
-                    if let Some(new_i) = part.simulation_index {
-                        if let Some(sim_state) = &self.sim_state {
-                            let mut state = sim_state.lock();
-                            let new_val = !state.part_outputs[new_i];
-                            state.part_outputs[new_i] = new_val;
-                            state.prev_outputs[new_i] = new_val;
-                        }
+                        SimClickMode::Record => self.toggle_trace(new_i, label),
+                        SimClickMode::Breakpoint => self.toggle_breakpoint(new_i, label),
+                        SimClickMode::BusBits => self.toggle_bus_bit(new_i, label),
                     }
                 }
             }
@@ -696,35 +944,8 @@ impl AppState {
     }
 }
 
-pub fn sort_by_position<T>(items: &mut Vec<T>, get_pos: impl Fn(&T) -> Pos2) {
-    // this function was made by ai
-    // im too stupid to figure ts out
-    let positions: Vec<Pos2> = items.iter().map(|i| get_pos(i)).collect();
-    let x_spread = positions.iter().map(|p| p.x).fold(f32::MIN, f32::max)
-        - positions.iter().map(|p| p.x).fold(f32::MAX, f32::min);
-    let y_spread = positions.iter().map(|p| p.y).fold(f32::MIN, f32::max)
-        - positions.iter().map(|p| p.y).fold(f32::MAX, f32::min);
-    items.sort_by(|a, b| {
-        let a_pos = get_pos(a);
-        let b_pos = get_pos(b);
-        if y_spread >= x_spread {
-            a_pos
-                .y
-                .partial_cmp(&b_pos.y)
-                .unwrap()
-                .then(a_pos.x.partial_cmp(&b_pos.x).unwrap())
-        } else {
-            a_pos
-                .x
-                .partial_cmp(&b_pos.x)
-                .unwrap()
-                .then(a_pos.y.partial_cmp(&b_pos.y).unwrap())
-        }
-    });
-}
-
 fn sort_ports_by_position(ports: &mut Vec<Port>, app: &AppState) {
-    sort_by_position(ports, |p| p.pos(app).unwrap_or_default());
+    sort_by_position(ports, |p| p.pos(&app.canvas_snapshot).unwrap_or_default());
 }
 
 fn format_with_commas(n: u64) -> String {
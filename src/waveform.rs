@@ -0,0 +1,445 @@
+use crate::colors::POWERED_COLOR;
+use crate::parts::{PartData, sort_by_position};
+use crate::state::AppState;
+use egui::{Align2, Color32, FontId, Pos2, Rect, Sense, Stroke, Vec2};
+use smlogic_core::vcd::write_vcd;
+use smlogic_core::waveform::Waveform;
+
+const NAME_WIDTH: f32 = 140.0;
+const ROW_HEIGHT: f32 = 24.0;
+const RULER_HEIGHT: f32 = 18.0;
+const MIN_LABEL_GAP: f32 = 60.0; // min pixels between tick numbers on the ruler
+const TICKS_PER_SECOND: f64 = 40.0;
+
+const HELP_TEXT: &str = "scroll to zoom, drag to pan, click to place cursor A, shift click for B, \
+click a name to stop recording it";
+const EMPTY_TEXT: &str = "Nothing recorded yet, add some signals above or use the \
+\"Record waveform\" click mode in the simulator.";
+
+const CURSOR_A_COLOR: Color32 = Color32::from_rgb(255, 200, 0);
+const CURSOR_B_COLOR: Color32 = Color32::from_rgb(0, 220, 220);
+
+/// how the timing diagram is scrolled/zoomed, plus the measurement cursors
+pub struct WaveformView {
+    pub px_per_tick: f32,
+    pub left_tick: f64, // tick at the left edge of the plot
+    pub follow: bool,   // keep the newest tick in view
+    pub cursor_a: Option<u64>,
+    pub cursor_b: Option<u64>,
+}
+
+impl WaveformView {
+    pub const NEW: Self = Self {
+        px_per_tick: 8.0,
+        left_tick: 0.0,
+        follow: true,
+        cursor_a: None,
+        cursor_b: None,
+    };
+}
+
+/// picks 1, 2 or 5 times a power of 10 so labels are at least `min` ticks apart
+fn ruler_step(min: f64) -> u64 {
+    let mut step = 1;
+    loop {
+        for mult in [1, 2, 5] {
+            if (step * mult) as f64 >= min {
+                return step * mult;
+            }
+        }
+        step *= 10;
+    }
+}
+
+impl AppState {
+    /// starts or stops recording a signal. its named after where it is in the module tree, the
+    /// same way probes are, so the same part in two module instances can be told apart.
+    /// `name` is only used if the part cant be found
+    pub fn toggle_trace(&mut self, index: usize, name: String) {
+        let Some(sim_state) = &self.sim_state else {
+            return;
+        };
+        let mut state = sim_state.lock();
+        if state.waveform.contains(index) {
+            state.waveform.remove_trace(index);
+            return;
+        }
+        // the module view swaps the canvas out, paths start from the top level one
+        let top_level = match &self.module_view {
+            Some(view) => &view.canvas,
+            None => &self.canvas_snapshot,
+        };
+        let mut labels = state
+            .part_paths
+            .get(index)
+            .map(|path| top_level.path_labels(path))
+            .unwrap_or_default();
+        let name = labels.pop().filter(|l| !l.is_empty()).unwrap_or(name);
+        let tree = labels.join(" > ");
+        let (tick, value) = (state.tick, state.part_outputs[index]);
+        state.waveform.add_trace(name, tree, index, tick, value);
+    }
+
+    fn io_signals(&self) -> Vec<(String, usize)> {
+        let mut io_parts: Vec<_> = self
+            .canvas_snapshot
+            .parts
+            .values()
+            .filter(|p| matches!(p.part_data, PartData::IO(_)))
+            .collect();
+        sort_by_position(&mut io_parts, |p| p.pos);
+        io_parts
+            .iter()
+            .filter_map(|p| Some((p.label.clone(), p.simulation_index?)))
+            .collect()
+    }
+
+    pub fn draw_waveform_panel(&mut self, ctx: &egui::Context) {
+        let Some(sim_state) = self.sim_state.clone() else {
+            return;
+        };
+        let io_signals = self.io_signals();
+        let important_signals: Vec<(String, String, usize)> = self
+            .important_gates
+            .iter()
+            .map(|g| (g.label.clone(), g.tree.clone(), g.simulation_index))
+            .collect();
+        let mut export_vcd = false;
+
+        egui::TopBottomPanel::bottom("waveforms")
+            .resizable(true)
+            .default_height(200.0)
+            .frame(
+                egui::Frame::new()
+                    .fill(self.color_pallet.base)
+                    .inner_margin(8.0),
+            )
+            .show(ctx, |ui| {
+                let view = &mut self.waveform_view;
+                ui.horizontal(|ui| {
+                    ui.heading("Waveforms");
+                    let mut state = sim_state.lock();
+                    let tick = state.tick;
+                    if ui
+                        .button("Add IO")
+                        .on_hover_text("Records all inputs and outputs")
+                        .clicked()
+                    {
+                        for (name, index) in &io_signals {
+                            let value = state.part_outputs[*index];
+                            let name = name.clone();
+                            state
+                                .waveform
+                                .add_trace(name, String::new(), *index, tick, value);
+                        }
+                    }
+                    if ui.button("Add important").clicked() {
+                        for (name, tree, index) in &important_signals {
+                            let value = state.part_outputs[*index];
+                            let (name, tree) = (name.clone(), tree.clone());
+                            state.waveform.add_trace(name, tree, *index, tick, value);
+                        }
+                    }
+                    if ui.button("Clear history").clicked() {
+                        let outputs = state.part_outputs.clone();
+                        state.waveform.clear_history(tick, &outputs);
+                        view.cursor_a = None;
+                        view.cursor_b = None;
+                    }
+                    if ui.button("Remove all").clicked() {
+                        state.waveform.traces.clear();
+                    }
+                    if ui
+                        .button("Export VCD")
+                        .on_hover_text("Saves the recorded signals to a .vcd file for GTKWave")
+                        .clicked()
+                    {
+                        export_vcd = true;
+                    }
+                    ui.separator();
+                    ui.checkbox(&mut view.follow, "Follow");
+                    ui.label("History:");
+                    let mut capacity = state.waveform.capacity;
+                    if ui
+                        .add(
+                            egui::DragValue::new(&mut capacity)
+                                .range(1..=1_000_000)
+                                .suffix(" ticks"),
+                        )
+                        .changed()
+                    {
+                        state.waveform.set_capacity(capacity);
+                    }
+                    drop(state);
+
+                    ui.separator();
+                    if let Some(a) = view.cursor_a {
+                        ui.colored_label(CURSOR_A_COLOR, format!("A: {}", a));
+                    }
+                    if let Some(b) = view.cursor_b {
+                        ui.colored_label(CURSOR_B_COLOR, format!("B: {}", b));
+                    }
+                    if let (Some(a), Some(b)) = (view.cursor_a, view.cursor_b) {
+                        let delta = a.abs_diff(b);
+                        ui.label(format!(
+                            "Δ {} ticks ({:.3}s)",
+                            delta,
+                            delta as f64 / TICKS_PER_SECOND
+                        ));
+                    }
+                });
+                ui.small(HELP_TEXT);
+                ui.separator();
+
+                // copy out just what we need so the sim isnt locked while drawing
+                let state = sim_state.lock();
+                let latest_tick = state.tick;
+                let range = state.waveform.tick_range();
+                let trace_count = state.waveform.traces.len();
+                drop(state);
+
+                if trace_count == 0 {
+                    ui.label(EMPTY_TEXT);
+                    return;
+                }
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    let size = Vec2::new(
+                        ui.available_width(),
+                        RULER_HEIGHT + ROW_HEIGHT * trace_count as f32,
+                    );
+                    let (rect, response) = ui.allocate_exact_size(size, Sense::click_and_drag());
+                    let plot_left = rect.left() + NAME_WIDTH;
+                    let plot_width = (rect.width() - NAME_WIDTH).max(1.0);
+
+                    // zoom around the mouse
+                    let hover = response.hover_pos();
+                    if let Some(pos) = hover
+                        && pos.x > plot_left
+                    {
+                        let scroll = ui.input(|i| i.smooth_scroll_delta.y);
+                        if scroll.abs() > 0.5 {
+                            let mouse_tick =
+                                view.left_tick + ((pos.x - plot_left) / view.px_per_tick) as f64;
+                            view.px_per_tick =
+                                (view.px_per_tick * (1.0 + scroll * 0.002)).clamp(0.01, 60.0);
+                            view.left_tick =
+                                mouse_tick - ((pos.x - plot_left) / view.px_per_tick) as f64;
+                        }
+                    }
+                    if response.dragged() {
+                        view.left_tick -= (response.drag_delta().x / view.px_per_tick) as f64;
+                        view.follow = false;
+                    }
+                    let visible_ticks = plot_width as f64 / view.px_per_tick as f64;
+                    if view.follow {
+                        view.left_tick = latest_tick as f64 + 1.0 - visible_ticks;
+                    }
+                    if let Some((start, _)) = range {
+                        view.left_tick = view.left_tick.max(start as f64 - visible_ticks * 0.5);
+                    }
+                    view.left_tick = view.left_tick.max(0.0);
+
+                    let first_tick = view.left_tick.floor().max(0.0) as u64;
+                    let last_tick = (view.left_tick + visible_ticks).ceil() as u64;
+                    let tick_x =
+                        |tick: f64| plot_left + ((tick - view.left_tick) as f32) * view.px_per_tick;
+
+                    let state = sim_state.lock();
+                    let rows: Vec<_> = state
+                        .waveform
+                        .traces
+                        .iter()
+                        .map(|t| {
+                            let shown_value = view
+                                .cursor_a
+                                .and_then(|a| t.value_at(a))
+                                .or_else(|| t.values.back().copied());
+                            (
+                                t.full_name(),
+                                t.index,
+                                t.runs(first_tick, last_tick),
+                                shown_value,
+                            )
+                        })
+                        .collect();
+                    drop(state);
+
+                    let painter = ui.painter_at(rect);
+                    let text_color = self.color_pallet.text;
+                    let grid_color = self.color_pallet.grid_lines;
+
+                    // ruler
+                    let step = ruler_step((MIN_LABEL_GAP / view.px_per_tick) as f64);
+                    let mut tick = first_tick / step * step;
+                    while tick <= last_tick {
+                        let x = tick_x(tick as f64);
+                        if x >= plot_left {
+                            painter.line_segment(
+                                [
+                                    Pos2::new(x, rect.top() + RULER_HEIGHT - 4.0),
+                                    Pos2::new(x, rect.bottom()),
+                                ],
+                                Stroke::new(1.0, grid_color.gamma_multiply(0.4)),
+                            );
+                            painter.text(
+                                Pos2::new(x + 2.0, rect.top()),
+                                Align2::LEFT_TOP,
+                                tick.to_string(),
+                                FontId::proportional(10.0),
+                                text_color,
+                            );
+                        }
+                        tick += step;
+                    }
+
+                    // rows
+                    let mut remove: Option<usize> = None;
+                    for (row, (name, index, runs, shown_value)) in rows.iter().enumerate() {
+                        let top = rect.top() + RULER_HEIGHT + row as f32 * ROW_HEIGHT;
+                        let name_rect = Rect::from_min_size(
+                            Pos2::new(rect.left(), top),
+                            Vec2::new(NAME_WIDTH, ROW_HEIGHT),
+                        );
+                        let name_hovered = hover.is_some_and(|p| name_rect.contains(p));
+                        let value_text = match shown_value {
+                            Some(true) => "1",
+                            Some(false) => "0",
+                            None => "-",
+                        };
+                        painter.text(
+                            name_rect.left_center() + Vec2::new(4.0, 0.0),
+                            Align2::LEFT_CENTER,
+                            format!("{} = {}", name, value_text),
+                            FontId::proportional(12.0),
+                            if name_hovered {
+                                Color32::RED
+                            } else {
+                                text_color
+                            },
+                        );
+                        if name_hovered && response.clicked() {
+                            remove = Some(*index);
+                        }
+
+                        let high = top + 4.0;
+                        let low = top + ROW_HEIGHT - 4.0;
+                        let mut last_y: Option<f32> = None;
+                        for (start, end, value) in runs {
+                            let x0 = tick_x(*start as f64).max(plot_left);
+                            let x1 = tick_x(*end as f64 + 1.0);
+                            let y = if *value { high } else { low };
+                            let color = if *value {
+                                POWERED_COLOR
+                            } else {
+                                Color32::from_gray(150)
+                            };
+                            if *value {
+                                painter.rect_filled(
+                                    Rect::from_min_max(Pos2::new(x0, high), Pos2::new(x1, low)),
+                                    0.0,
+                                    POWERED_COLOR.gamma_multiply(0.2),
+                                );
+                            }
+                            if let Some(prev) = last_y
+                                && prev != y
+                            {
+                                painter.line_segment(
+                                    [Pos2::new(x0, prev), Pos2::new(x0, y)],
+                                    Stroke::new(1.5, color),
+                                );
+                            }
+                            painter.line_segment(
+                                [Pos2::new(x0, y), Pos2::new(x1, y)],
+                                Stroke::new(1.5, color),
+                            );
+                            last_y = Some(y);
+                        }
+                        painter.line_segment(
+                            [
+                                Pos2::new(rect.left(), top + ROW_HEIGHT),
+                                Pos2::new(rect.right(), top + ROW_HEIGHT),
+                            ],
+                            Stroke::new(1.0, grid_color.gamma_multiply(0.4)),
+                        );
+                    }
+
+                    // cursors
+                    if let Some(pos) = hover
+                        && pos.x > plot_left
+                    {
+                        let hovered_tick = (view.left_tick
+                            + ((pos.x - plot_left) / view.px_per_tick) as f64)
+                            .floor()
+                            .max(0.0) as u64;
+                        if response.clicked() {
+                            if ui.input(|i| i.modifiers.shift) {
+                                view.cursor_b = Some(hovered_tick);
+                            } else {
+                                view.cursor_a = Some(hovered_tick);
+                            }
+                        }
+                        painter.text(
+                            Pos2::new(rect.left() + 4.0, rect.top()),
+                            Align2::LEFT_TOP,
+                            format!("tick {}", hovered_tick),
+                            FontId::proportional(10.0),
+                            text_color,
+                        );
+                    }
+                    for (cursor, color) in [
+                        (view.cursor_a, CURSOR_A_COLOR),
+                        (view.cursor_b, CURSOR_B_COLOR),
+                    ] {
+                        if let Some(tick) = cursor {
+                            let x = tick_x(tick as f64 + 0.5);
+                            if x >= plot_left {
+                                painter.line_segment(
+                                    [Pos2::new(x, rect.top()), Pos2::new(x, rect.bottom())],
+                                    Stroke::new(1.5, color),
+                                );
+                            }
+                        }
+                    }
+
+                    if let Some(index) = remove {
+                        sim_state.lock().waveform.remove_trace(index);
+                    }
+                });
+            });
+
+        if export_vcd {
+            // copied so the sim isnt stuck waiting while the file dialog is open
+            let waveform = sim_state.lock().waveform.clone();
+            self.export_vcd(&waveform);
+        }
+    }
+
+    fn export_vcd(&mut self, waveform: &Waveform) {
+        if waveform.traces.is_empty() {
+            self.toasts.error("Nothing is being recorded!");
+            return;
+        }
+        let top = self
+            .current_module_path
+            .as_ref()
+            .and_then(|p| p.file_stem())
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "top".to_string());
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Value Change Dump", &["vcd"])
+            .set_file_name(format!("{}.vcd", top))
+            .save_file()
+        else {
+            return;
+        };
+        match std::fs::write(&path, write_vcd(waveform, &top)) {
+            Ok(()) => {
+                self.toasts.success("Exported VCD!");
+            }
+            Err(e) => {
+                self.toasts.error(format!("Failed to write VCD: {}", e));
+            }
+        }
+    }
+}